@@ -0,0 +1,96 @@
+use std::net::{IpAddr, Ipv4Addr, Ipv6Addr};
+
+use abuseipdb2::{
+    lookup::{Builder, SharedLookup},
+    mapped::{self, MappedSnapshot, Record},
+};
+use criterion::{black_box, criterion_group, criterion_main, Criterion};
+
+const ENTRIES: u32 = 100_000;
+
+/// Spreads listed addresses over the whole space, like a real blacklist.
+fn listed_v4(i: u32) -> Ipv4Addr {
+    Ipv4Addr::from(i.wrapping_mul(2_654_435_761) | 1)
+}
+
+fn listed_v6(i: u32) -> Ipv6Addr {
+    Ipv6Addr::from(((i as u128).wrapping_mul(0x9e37_79b9_7f4a_7c15) << 64) | 1)
+}
+
+fn bench_lookup(c: &mut Criterion) {
+    let mut builder = Builder::new();
+    for i in 0..ENTRIES {
+        builder = builder
+            .address(listed_v4(i).into(), i % 100 + 1)
+            .address(listed_v6(i).into(), i % 100 + 1);
+    }
+    let lookup = builder.build();
+    let encoded = mapped::encode(
+        None,
+        (0..ENTRIES).flat_map(|i| {
+            [listed_v4(i).into(), listed_v6(i).into()].map(|address| Record {
+                address,
+                score: i % 100 + 1,
+                last_reported_at: None,
+            })
+        }),
+    );
+    let mapped = MappedSnapshot::from_bytes(encoded).unwrap();
+    let shared = SharedLookup::new(lookup.clone());
+
+    let hits_v4: Vec<IpAddr> = (0..1024).map(|i| listed_v4(i * 97).into()).collect();
+    // listed addresses are odd, so the one before is not listed
+    let misses_v4: Vec<IpAddr> = (0..1024)
+        .map(|i| Ipv4Addr::from(u32::from(listed_v4(i * 97)) - 1).into())
+        .collect();
+    let hits_v6: Vec<IpAddr> = (0..1024).map(|i| listed_v6(i * 97).into()).collect();
+    let misses_v6: Vec<IpAddr> = (0..1024)
+        .map(|i| Ipv6Addr::from(u128::from(listed_v6(i * 97)) - 1).into())
+        .collect();
+
+    let mut group = c.benchmark_group("lookup");
+    for (name, addresses) in [
+        ("v4 hit", &hits_v4),
+        ("v4 miss", &misses_v4),
+        ("v6 hit", &hits_v6),
+        ("v6 miss", &misses_v6),
+    ] {
+        let mut i = 0;
+        group.bench_function(name, |b| {
+            b.iter(|| {
+                i = (i + 1) % addresses.len();
+                lookup.get(black_box(addresses[i]))
+            })
+        });
+    }
+    let mut i = 0;
+    group.bench_function("shared v4 hit", |b| {
+        b.iter(|| {
+            i = (i + 1) % hits_v4.len();
+            shared.get(black_box(hits_v4[i]))
+        })
+    });
+    for (name, addresses) in [("mapped v4 hit", &hits_v4), ("mapped v6 hit", &hits_v6)] {
+        let mut i = 0;
+        group.bench_function(name, |b| {
+            b.iter(|| {
+                i = (i + 1) % addresses.len();
+                mapped.score(black_box(addresses[i]))
+            })
+        });
+    }
+    group.finish();
+
+    c.bench_function("build 100k", |b| {
+        b.iter(|| {
+            (0..ENTRIES)
+                .fold(Builder::new(), |builder, i| {
+                    builder.address(listed_v4(i).into(), i % 100 + 1)
+                })
+                .build()
+        })
+    });
+}
+
+criterion_group!(benches, bench_lookup);
+criterion_main!(benches);
@@ -0,0 +1,53 @@
+use std::net::{IpAddr, Ipv4Addr};
+
+use abuseipdb2::honeypot::{Honeypot, Service};
+use tokio::{
+    io::{AsyncReadExt, AsyncWriteExt},
+    net::TcpStream,
+    sync::mpsc,
+};
+
+#[tokio::main]
+async fn main() {
+    let (sender, mut events) = mpsc::unbounded_channel();
+    let addresses = Honeypot::new()
+        .address(IpAddr::V4(Ipv4Addr::LOCALHOST))
+        .listen(0, Service::Ssh)
+        .listen(0, Service::Telnet)
+        .listen(0, Service::Http)
+        .start(sender)
+        .await
+        .unwrap();
+
+    // a client that only grabs the banner
+    let mut stream = TcpStream::connect(addresses[0]).await.unwrap();
+    let mut banner = [0; 64];
+    let read = stream.read(&mut banner).await.unwrap();
+    print!("banner: {}", String::from_utf8_lossy(&banner[..read]));
+    drop(stream);
+
+    // a bot trying default credentials
+    let mut stream = TcpStream::connect(addresses[1]).await.unwrap();
+    stream.write_all(b"root\r\n").await.unwrap();
+    drop(stream);
+
+    // a web exploit
+    let mut stream = TcpStream::connect(addresses[2]).await.unwrap();
+    stream
+        .write_all(
+            b"GET /cgi-bin/luci/;stok=/locale?form=country HTTP/1.1\r\nHost: 127.0.0.1\r\n\r\n",
+        )
+        .await
+        .unwrap();
+    let mut response = String::new();
+    stream.read_to_string(&mut response).await.unwrap();
+    println!("{}", response.lines().next().unwrap_or_default());
+
+    for _ in 0..3 {
+        let event = events.recv().await.unwrap();
+        println!(
+            "{} {:?}: {}",
+            event.address, event.categories, event.evidence
+        );
+    }
+}
@@ -0,0 +1,27 @@
+use abuseipdb2::{mapped, mapped::MappedSnapshot, Client};
+
+/// Fetches the blacklist into a binary snapshot, then maps it and looks up
+/// an address, as every worker process would.
+#[tokio::main]
+async fn main() {
+    let client = Client::new(std::env::var("ABUSEIPDB_KEY").unwrap_or_default());
+    let path = "blacklist.bin";
+
+    let response = client
+        .blacklist(90, Some(10_000), None, None, None)
+        .await
+        .unwrap();
+    mapped::save(path, &mapped::encode_blacklist(&response)).unwrap();
+
+    let snapshot = MappedSnapshot::open(path).unwrap();
+    let address = std::env::args()
+        .nth(1)
+        .unwrap_or("127.0.0.1".to_string())
+        .parse()
+        .unwrap();
+    println!(
+        "{} addresses, {address}: {:?}",
+        snapshot.len(),
+        snapshot.get(address)
+    );
+}
@@ -0,0 +1,16 @@
+use abuseipdb2::{export::nftables::Nftables, snapshot::Snapshot};
+
+fn main() {
+    let previous = Snapshot::from_plaintext(
+        "192.0.2.0\n192.0.2.1\n192.0.2.2\n192.0.2.3\n198.51.100.9\n2001:db8::1\n",
+        100,
+    );
+    let current = Snapshot::from_plaintext(
+        "192.0.2.0\n192.0.2.1\n192.0.2.2\n192.0.2.3\n192.0.2.4\n203.0.113.7\n2001:db8::1\n",
+        100,
+    );
+
+    let nftables = Nftables::new();
+    println!("{}", nftables.render(&current));
+    println!("{}", nftables.render_delta(&previous, &current));
+}
@@ -1,4 +1,4 @@
-use std::{net::IpAddr, str::FromStr, vec};
+use std::{net::IpAddr, str::FromStr};
 
 use abuseipdb2::{types::Category, Client};
 
@@ -9,7 +9,7 @@ async fn main() {
     let report = client
         .report(
             &IpAddr::from_str("127.0.0.1").unwrap(),
-            &vec![Category::OpenProxy],
+            &[Category::OpenProxy],
             None,
             None,
         )
@@ -0,0 +1,26 @@
+use std::{net::IpAddr, str::FromStr};
+
+use abuseipdb2::{endpoints::report::Outcome, sanitize::Sanitizer, types::Category, Client};
+
+#[tokio::main]
+async fn main() {
+    let client = Client::new(std::env::var("ABUSEIPDB_KEY").unwrap_or_default());
+
+    let outcome = client
+        .report_builder(IpAddr::from_str("203.0.113.7").unwrap())
+        .category(Category::BruteForceCredential)
+        .category(Category::SshAbuse)
+        .comment(
+            "sshd[1234]: Failed password for invalid user admin from 203.0.113.7 port 22 \
+             on bastion.example.com (10.0.0.5), alerts to ops@example.com",
+        )
+        .sanitizer(Sanitizer::default().domain("example.com"))
+        .dry_run(true)
+        .send()
+        .await
+        .unwrap();
+
+    if let Outcome::DryRun(request) = outcome {
+        println!("{}", request.comment().unwrap_or_default());
+    }
+}
@@ -0,0 +1,23 @@
+use abuseipdb2::{snapshot::Snapshot, Client};
+
+/// Fetches the blacklist, prints what changed since the last run and keeps
+/// the new snapshot for the next one.
+#[tokio::main]
+async fn main() {
+    let client = Client::new(std::env::var("ABUSEIPDB_KEY").unwrap_or_default());
+    let path = "blacklist-snapshot.json";
+
+    let previous = Snapshot::load(path).unwrap();
+    let response = client
+        .blacklist(90, Some(10_000), None, None, None)
+        .await
+        .unwrap();
+    let current = Snapshot::from_response(&response);
+
+    let diff = current.diff(&previous);
+    print!("{}", diff.change_log());
+    if !diff.is_empty() {
+        diff.append_to("blacklist-changes.jsonl").unwrap();
+    }
+    current.save(path).unwrap();
+}
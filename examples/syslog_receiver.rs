@@ -0,0 +1,80 @@
+use std::sync::Arc;
+
+use abuseipdb2::{
+    ingest::syslog::{Receiver, Rule},
+    types::Category,
+};
+use tokio::{
+    io::AsyncWriteExt,
+    net::{TcpListener, TcpStream, UdpSocket},
+    sync::mpsc,
+};
+
+#[tokio::main]
+async fn main() {
+    let receiver = Arc::new(
+        Receiver::new()
+            .rule(
+                Rule::new(
+                    Some("sshd"),
+                    r"Failed password for .* from (?P<ip>\S+)",
+                    &[Category::BruteForceCredential, Category::SshAbuse],
+                    "failed SSH login",
+                )
+                .unwrap(),
+            )
+            .rule(
+                Rule::new(
+                    Some("openvpn"),
+                    r"(?P<ip>[\d.]+):\d+ TLS Auth Error",
+                    &[Category::BruteForceCredential],
+                    "failed VPN login",
+                )
+                .unwrap(),
+            ),
+    );
+    let (sender, mut events) = mpsc::unbounded_channel();
+
+    let udp = UdpSocket::bind("127.0.0.1:0").await.unwrap();
+    let udp_address = udp.local_addr().unwrap();
+    let tcp = TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let tcp_address = tcp.local_addr().unwrap();
+
+    let udp_receiver = receiver.clone();
+    let udp_sender = sender.clone();
+    tokio::spawn(async move { udp_receiver.serve_udp(udp, udp_sender).await });
+    tokio::spawn(receiver.serve_tcp(tcp, sender));
+
+    // RFC 3164 over UDP
+    let client = UdpSocket::bind("127.0.0.1:0").await.unwrap();
+    client
+        .send_to(
+            b"<38>Mar  4 09:12:01 bastion sshd[812]: Failed password for root from 203.0.113.7 port 52211 ssh2",
+            udp_address,
+        )
+        .await
+        .unwrap();
+
+    // RFC 5424 over TCP, octet counted and newline delimited
+    let message = "<38>1 2024-03-04T09:12:02.513Z gw openvpn 77 - [meta sequenceId=\"1\"] 198.51.100.23:41012 TLS Auth Error";
+    let mut stream = TcpStream::connect(tcp_address).await.unwrap();
+    stream
+        .write_all(format!("{} {message}", message.len()).as_bytes())
+        .await
+        .unwrap();
+    stream
+        .write_all(b"<38>1 2024-03-04T09:12:03Z bastion sshd - - - Failed password for invalid user admin from 2001:db8::7 port 4022 ssh2\n")
+        .await
+        .unwrap();
+
+    for _ in 0..3 {
+        let event = events.recv().await.unwrap();
+        let request = event.to_request();
+        println!(
+            "{} {:?}: {}",
+            request.address(),
+            request.categories(),
+            request.comment().unwrap_or_default()
+        );
+    }
+}
@@ -0,0 +1,50 @@
+use std::net::IpAddr;
+
+use abuseipdb2::{layer::ReportLayer, Client};
+use tracing::{info, warn};
+use tracing_subscriber::{
+    filter::LevelFilter, layer::SubscriberExt, util::SubscriberInitExt, Layer,
+};
+
+#[tokio::main]
+async fn main() {
+    // without a key, reports go nowhere and show up as errors below
+    let client = match std::env::var("ABUSEIPDB_KEY") {
+        Ok(key) => Client::new(key),
+        Err(_) => Client::new_with_base("", "http://127.0.0.1:9/").unwrap(),
+    };
+
+    let (layer, worker) = ReportLayer::new(client);
+    tracing_subscriber::registry()
+        .with(tracing_subscriber::fmt::layer().with_filter(LevelFilter::INFO))
+        .with(layer)
+        .init();
+
+    let worker = tokio::spawn(worker.run(|request, e| {
+        println!(
+            "not reported {} {:?} {:?}: {e}",
+            request.address(),
+            request.categories(),
+            request.comment()
+        )
+    }));
+
+    let address: IpAddr = "203.0.113.7".parse().unwrap();
+    info!(%address, "login page served");
+    for attempt in 1..=3 {
+        warn!(
+            client_ip = %address,
+            abuse_category = "18,21",
+            "wp-login.php brute force, attempt {attempt} for admin@example.com"
+        );
+    }
+    warn!(
+        client_ip = "2001:db8::7",
+        abuse_category = "SqlInjection",
+        "id=1' OR '1'='1"
+    );
+
+    // dropping the subscriber would close the queue; give the worker a moment
+    tokio::time::sleep(std::time::Duration::from_secs(1)).await;
+    worker.abort();
+}
@@ -0,0 +1,186 @@
+use std::{
+    collections::{BTreeSet, HashMap},
+    net::IpAddr,
+};
+
+use chrono::{DateTime, Duration, Utc};
+
+use crate::{
+    endpoints::report::Request,
+    event::Event,
+    sanitize::{truncate, MAX_COMMENT_LENGTH},
+};
+
+fn plural(noun: &str, count: usize) -> String {
+    if count == 1 {
+        noun.to_string()
+    } else if noun.ends_with('s') || noun.ends_with('x') || noun.ends_with("ch") {
+        format!("{noun}es")
+    } else {
+        format!("{noun}s")
+    }
+}
+
+fn format_span(span: Duration) -> String {
+    if span < Duration::minutes(1) {
+        format!("{} s", span.num_seconds())
+    } else if span < Duration::hours(2) {
+        format!("{} min", span.num_minutes())
+    } else {
+        format!("{} h", span.num_hours())
+    }
+}
+
+struct KindSummary<'a> {
+    kind: &'a str,
+    count: usize,
+    /// Distinct target values per noun, in first-seen order.
+    targets: Vec<(&'a str, BTreeSet<&'a str>)>,
+}
+
+/// Summarizes events of one IP, e.g.
+/// `"37 failed SSH logins for 12 users over 10 min"`.
+pub fn summarize(events: &[Event]) -> String {
+    let mut kinds: Vec<KindSummary> = Vec::new();
+    for event in events {
+        let index = match kinds.iter().position(|k| k.kind == event.kind) {
+            Some(index) => index,
+            None => {
+                kinds.push(KindSummary {
+                    kind: &event.kind,
+                    count: 0,
+                    targets: Vec::new(),
+                });
+                kinds.len() - 1
+            }
+        };
+        let summary = &mut kinds[index];
+        summary.count += 1;
+        if let Some(target) = &event.target {
+            let targets = &mut summary.targets;
+            match targets.iter_mut().find(|(noun, _)| *noun == target.noun) {
+                Some((_, values)) => {
+                    values.insert(&target.value);
+                }
+                None => targets.push((&target.noun, BTreeSet::from([target.value.as_str()]))),
+            }
+        }
+    }
+
+    let mut parts = Vec::new();
+    for summary in kinds {
+        let mut part = format!("{} {}", summary.count, plural(summary.kind, summary.count));
+        let targets: Vec<String> = summary
+            .targets
+            .into_iter()
+            .map(|(noun, values)| format!("{} {}", values.len(), plural(noun, values.len())))
+            .collect();
+        if !targets.is_empty() {
+            part.push_str(" for ");
+            part.push_str(&targets.join(" and "));
+        }
+        parts.push(part);
+    }
+
+    let mut summary = parts.join(", ");
+    let first = events.iter().map(|e| e.timestamp).min();
+    let last = events.iter().map(|e| e.timestamp).max();
+    if let (Some(first), Some(last)) = (first, last) {
+        if last > first {
+            summary.push_str(" over ");
+            summary.push_str(&format_span(last - first));
+        }
+    }
+    summary
+}
+
+/// Collects events per IP over a sliding window and turns them into one
+/// consolidated report once an IP crosses a threshold.
+#[derive(Debug, Clone)]
+pub struct Aggregator {
+    window: Duration,
+    threshold: usize,
+    samples: usize,
+    buckets: HashMap<IpAddr, Vec<Event>>,
+}
+
+impl Aggregator {
+    /// Emits a report once an IP has `threshold` events within `window`.
+    pub fn new(window: Duration, threshold: usize) -> Self {
+        Self {
+            window,
+            threshold: threshold.max(1),
+            samples: 3,
+            buckets: HashMap::new(),
+        }
+    }
+
+    /// How many evidence lines to append to the summary, defaults to 3.
+    pub fn samples(mut self, samples: usize) -> Self {
+        self.samples = samples;
+        self
+    }
+
+    /// Adds an event, returning a report if its IP crossed the threshold.
+    /// The IP's bucket is emptied when a report is returned.
+    pub fn push(&mut self, event: Event) -> Option<Request> {
+        let window = self.window;
+        let address = event.address;
+        let now = event.timestamp;
+
+        let bucket = self.buckets.entry(address).or_default();
+        bucket.push(event);
+        bucket.retain(|e| e.timestamp + window > now);
+
+        if bucket.len() < self.threshold {
+            return None;
+        }
+
+        let events = self.buckets.remove(&address)?;
+        self.consolidate(&events)
+    }
+
+    /// Drops events that fell out of the window, e.g. from a timer.
+    pub fn expire(&mut self, now: DateTime<Utc>) {
+        let window = self.window;
+        self.buckets.retain(|_, bucket| {
+            bucket.retain(|e| e.timestamp + window > now);
+            !bucket.is_empty()
+        });
+    }
+
+    /// Number of events currently held for an IP.
+    pub fn pending(&self, address: &IpAddr) -> usize {
+        self.buckets.get(address).map_or(0, Vec::len)
+    }
+
+    /// Combines events of one IP into a single report: union of their
+    /// categories, first-seen timestamp and a summarized comment.
+    pub fn consolidate(&self, events: &[Event]) -> Option<Request> {
+        let address = events.first()?.address;
+
+        let mut categories = Vec::new();
+        for event in events {
+            for category in &event.categories {
+                if !categories.contains(category) {
+                    categories.push(*category);
+                }
+            }
+        }
+
+        let mut comment = summarize(events);
+        for event in events.iter().take(self.samples) {
+            if !event.evidence.is_empty() {
+                comment.push('\n');
+                comment.push_str(&event.evidence);
+            }
+        }
+
+        let mut request =
+            Request::new(address, categories).with_comment(truncate(&comment, MAX_COMMENT_LENGTH));
+        if let Some(first_seen) = events.iter().map(|e| e.timestamp).min() {
+            request = request.with_timestamp(first_seen);
+        }
+        Some(request)
+    }
+}
@@ -0,0 +1,228 @@
+use std::{
+    fs::{self, File, OpenOptions},
+    net::IpAddr,
+    path::{Path, PathBuf},
+    process::ExitCode,
+    sync::Mutex,
+};
+
+use abuseipdb2::{
+    dedup::{Deduplicator, Outcome, Policy},
+    endpoints::report::Request,
+    sanitize::Sanitizer,
+    spool::{self, DrainOptions, Spool},
+    types::parse_categories,
+    Client,
+};
+
+const DEFAULT_STATE_DIR: &str = "/var/lib/abuseipdb-fail2ban";
+
+const USAGE: &str = "\
+usage: abuseipdb-fail2ban [--key <key>] [--state-dir <dir>] <command>
+
+commands:
+  ban <ip> <categories> [<matches>]   report an IP (fail2ban's actionban)
+  flush                               send reports queued while the API was down
+  action-config                       print a fail2ban action.d config
+
+The API key is read from --key or $ABUSEIPDB_KEY.";
+
+const ACTION_CONFIG: &str = "\
+# Fail2Ban action reporting banned IPs to AbuseIPDB, generated by
+# abuseipdb-fail2ban. Save as /etc/fail2ban/action.d/abuseipdb-rs.conf and
+# use it in a jail next to the banning action:
+#
+#   action = %(action_)s
+#            abuseipdb-rs[abuseipdb_apikey=\"<key>\", abuseipdb_category=\"18,22\"]
+#
+# Comments are scrubbed of email addresses, private IPs and usernames,
+# repeat reports within 15 minutes are skipped and reports are queued in
+# <abuseipdb_state_dir> while the API is unreachable.
+
+[Definition]
+
+actionstart = <abuseipdb_bin> --key <abuseipdb_apikey> --state-dir <abuseipdb_state_dir> flush
+
+actionstop =
+
+actioncheck =
+
+actionban = <abuseipdb_bin> --key <abuseipdb_apikey> --state-dir <abuseipdb_state_dir> ban <ip> \"<abuseipdb_category>\" \"<matches>\"
+
+actionunban =
+
+[Init]
+
+abuseipdb_apikey =
+
+abuseipdb_category =
+
+abuseipdb_bin = /usr/local/bin/abuseipdb-fail2ban
+
+abuseipdb_state_dir = /var/lib/abuseipdb-fail2ban
+";
+
+struct Options {
+    key: Option<String>,
+    state_dir: PathBuf,
+    command: Vec<String>,
+}
+
+fn parse_args() -> Result<Options, String> {
+    let mut args = std::env::args().skip(1);
+    let mut options = Options {
+        key: std::env::var("ABUSEIPDB_KEY").ok(),
+        state_dir: PathBuf::from(DEFAULT_STATE_DIR),
+        command: Vec::new(),
+    };
+
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--key" => options.key = Some(args.next().ok_or("--key needs a value")?),
+            "--state-dir" => {
+                options.state_dir = args.next().ok_or("--state-dir needs a value")?.into()
+            }
+            "-h" | "--help" => return Err(String::new()),
+            _ => {
+                options.command.push(arg);
+                options.command.extend(args.by_ref());
+            }
+        }
+    }
+
+    Ok(options)
+}
+
+fn client(options: &Options) -> Result<Client, String> {
+    match options.key.as_deref() {
+        Some(key) if !key.is_empty() => Ok(Client::new(key)),
+        _ => Err("no API key, pass --key or set ABUSEIPDB_KEY".to_string()),
+    }
+}
+
+/// Locks the state directory until the returned file is dropped. fail2ban
+/// starts a process per ban and runs jails in parallel, so without it they
+/// would overwrite each other's dedup state, append to a compacted spool
+/// file or send the spool twice.
+fn lock_state(state_dir: &Path) -> std::io::Result<File> {
+    fs::create_dir_all(state_dir)?;
+    let lock = OpenOptions::new()
+        .create(true)
+        .truncate(false)
+        .write(true)
+        .open(state_dir.join("lock"))?;
+    lock.lock()?;
+    Ok(lock)
+}
+
+fn open_spool(state_dir: &Path) -> abuseipdb2::Result<Mutex<Spool>> {
+    Ok(Mutex::new(Spool::open(state_dir.join("spool.jsonl"))?))
+}
+
+async fn flush(client: &Client, spool: &Mutex<Spool>) -> abuseipdb2::Result<()> {
+    let drained = spool::drain(spool, client, &DrainOptions::default()).await?;
+    if drained.sent > 0 || drained.dropped > 0 {
+        eprintln!(
+            "sent {} queued reports, dropped {}",
+            drained.sent, drained.dropped
+        );
+    }
+    Ok(())
+}
+
+async fn ban(options: &Options, args: &[String]) -> Result<(), String> {
+    let [address, categories, rest @ ..] = args else {
+        return Err("ban needs <ip> <categories> [<matches>]".to_string());
+    };
+    let address: IpAddr = address
+        .parse()
+        .map_err(|_| format!("invalid IP address {address:?}"))?;
+    let categories = parse_categories(categories)
+        .filter(|c| !c.is_empty())
+        .ok_or_else(|| format!("invalid categories {categories:?}"))?;
+
+    let mut request = Request::new(address, categories);
+    let comment = Sanitizer::default().sanitize(&rest.join(" "));
+    if !comment.is_empty() {
+        request = request.with_comment(comment);
+    }
+
+    let client = client(options)?;
+    let _lock = lock_state(&options.state_dir).map_err(|e| e.to_string())?;
+    let spool = open_spool(&options.state_dir).map_err(|e| e.to_string())?;
+    let mut dedup = Deduplicator::open(options.state_dir.join("dedup.json"), Policy::Skip)
+        .map_err(|e| e.to_string())?;
+
+    match dedup.report(&client, request.clone()).await {
+        Ok(Outcome::Sent(response)) => {
+            eprintln!(
+                "reported {address}, confidence score {}",
+                response.abuse_confidence_score()
+            );
+            // the API is reachable again, send what piled up meanwhile
+            if !spool.lock().unwrap().is_empty() {
+                flush(&client, &spool).await.map_err(|e| e.to_string())?;
+            }
+            Ok(())
+        }
+        Ok(Outcome::Skipped(skipped)) => {
+            eprintln!(
+                "skipped {address}, already reported at {}",
+                skipped.last_reported_at
+            );
+            Ok(())
+        }
+        // the API is unreachable, rate limited or failing on its side
+        Err(e) if e.is_retryable() => {
+            spool
+                .lock()
+                .unwrap()
+                .push(request)
+                .map_err(|e| e.to_string())?;
+            eprintln!("queued {address}: {e}");
+            Ok(())
+        }
+        Err(e) => Err(e.to_string()),
+    }
+}
+
+async fn run(options: Options) -> Result<(), String> {
+    match options.command.first().map(String::as_str) {
+        Some("ban") => ban(&options, &options.command[1..]).await,
+        Some("flush") => {
+            let client = client(&options)?;
+            let _lock = lock_state(&options.state_dir).map_err(|e| e.to_string())?;
+            let spool = open_spool(&options.state_dir).map_err(|e| e.to_string())?;
+            flush(&client, &spool).await.map_err(|e| e.to_string())
+        }
+        Some("action-config") => {
+            print!("{ACTION_CONFIG}");
+            Ok(())
+        }
+        Some(command) => Err(format!("unknown command {command:?}")),
+        None => Err(String::new()),
+    }
+}
+
+fn main() -> ExitCode {
+    let result = match parse_args() {
+        Ok(options) => tokio::runtime::Builder::new_current_thread()
+            .enable_all()
+            .build()
+            .unwrap()
+            .block_on(run(options)),
+        Err(e) => Err(e),
+    };
+
+    match result {
+        Ok(()) => ExitCode::SUCCESS,
+        Err(e) if e.is_empty() => {
+            eprintln!("{USAGE}");
+            ExitCode::FAILURE
+        }
+        Err(e) => {
+            eprintln!("abuseipdb-fail2ban: {e}");
+            ExitCode::FAILURE
+        }
+    }
+}
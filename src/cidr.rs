@@ -0,0 +1,302 @@
+use std::{
+    cmp::Reverse,
+    collections::BTreeMap,
+    net::{IpAddr, Ipv4Addr, Ipv6Addr},
+};
+
+use ipnetwork::IpNetwork;
+
+use crate::{endpoints::blacklist::Response, snapshot::Snapshot};
+
+/// The number of addresses with `host_bits` host bits, minus one, so the
+/// whole IPv6 space does not overflow.
+fn span(host_bits: u32) -> u128 {
+    if host_bits == 128 {
+        u128::MAX
+    } else {
+        (1 << host_bits) - 1
+    }
+}
+
+fn bits(ipv6: bool) -> u32 {
+    if ipv6 {
+        128
+    } else {
+        32
+    }
+}
+
+fn to_network(start: u128, prefix: u8, ipv6: bool) -> IpNetwork {
+    let address = if ipv6 {
+        IpAddr::V6(Ipv6Addr::from(start))
+    } else {
+        IpAddr::V4(Ipv4Addr::from(start as u32))
+    };
+    IpNetwork::new(address, prefix).unwrap()
+}
+
+/// The first address of a network as an integer, with the family.
+fn to_integer(network: &IpNetwork) -> (u128, bool) {
+    match network.network() {
+        IpAddr::V4(address) => (u32::from(address) as u128, false),
+        IpAddr::V6(address) => (u128::from(address), true),
+    }
+}
+
+/// Covers `start..=end` with the fewest aligned prefixes.
+fn range_to_prefixes(mut start: u128, end: u128, bits: u32, prefixes: &mut Vec<(u128, u8)>) {
+    loop {
+        let mut host_bits = start.trailing_zeros().min(bits);
+        while span(host_bits) > end - start {
+            host_bits -= 1;
+        }
+        prefixes.push((start, (bits - host_bits) as u8));
+
+        match start
+            .checked_add(span(host_bits))
+            .and_then(|s| s.checked_add(1))
+        {
+            Some(next) if next <= end => start = next,
+            _ => return,
+        }
+    }
+}
+
+/// Merges overlapping and adjacent ranges of one family into prefixes.
+fn collapse_ranges(mut ranges: Vec<(u128, u128)>, ipv6: bool) -> Vec<IpNetwork> {
+    ranges.sort_unstable();
+
+    let mut prefixes = Vec::new();
+    let mut ranges = ranges.into_iter();
+    let Some((mut start, mut end)) = ranges.next() else {
+        return Vec::new();
+    };
+    for (next_start, next_end) in ranges {
+        if end.checked_add(1).is_some_and(|after| next_start > after) {
+            range_to_prefixes(start, end, bits(ipv6), &mut prefixes);
+            start = next_start;
+        }
+        end = end.max(next_end);
+    }
+    range_to_prefixes(start, end, bits(ipv6), &mut prefixes);
+
+    prefixes
+        .into_iter()
+        .map(|(start, prefix)| to_network(start, prefix, ipv6))
+        .collect()
+}
+
+/// Merges overlapping and adjacent networks into the fewest prefixes
+/// covering exactly the same addresses. IPv4 prefixes come first, each
+/// family in ascending order.
+pub fn collapse<I: IntoIterator<Item = IpNetwork>>(networks: I) -> Vec<IpNetwork> {
+    let mut v4 = Vec::new();
+    let mut v6 = Vec::new();
+    for network in networks {
+        let (start, ipv6) = to_integer(&network);
+        let end = start + span(bits(ipv6) - network.prefix() as u32);
+        if ipv6 {
+            v6.push((start, end));
+        } else {
+            v4.push((start, end));
+        }
+    }
+
+    let mut networks = collapse_ranges(v4, false);
+    networks.extend(collapse_ranges(v6, true));
+    networks
+}
+
+/// Merges adjacent addresses into the fewest prefixes covering exactly the
+/// same addresses, e.g. `10.0.0.0` to `10.0.0.3` into `10.0.0.0/30`.
+pub fn aggregate<I: IntoIterator<Item = IpAddr>>(addresses: I) -> Vec<IpNetwork> {
+    collapse(addresses.into_iter().map(IpNetwork::from))
+}
+
+/// A network listed as a whole although only some of its addresses are.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Step {
+    pub network: IpNetwork,
+    /// Listed addresses inside the network.
+    pub listed: u128,
+    /// Addresses the step newly covers, which were not covered before.
+    pub collateral: u128,
+}
+
+/// The result of a [`Lossy`] aggregation.
+#[derive(Debug, Clone, Default)]
+pub struct Aggregation {
+    networks: Vec<IpNetwork>,
+    steps: Vec<Step>,
+}
+
+impl Aggregation {
+    pub fn networks(&self) -> &[IpNetwork] {
+        &self.networks
+    }
+
+    /// The lossy steps taken, IPv4 first, each family from the longest
+    /// prefix to the shortest.
+    pub fn steps(&self) -> &[Step] {
+        &self.steps
+    }
+
+    /// Unlisted addresses covered by the networks.
+    pub fn collateral(&self) -> u128 {
+        self.steps
+            .iter()
+            .fold(0u128, |total, step| total.saturating_add(step.collateral))
+    }
+
+    /// Each network with the highest score of the listed addresses inside
+    /// it.
+    pub fn scored(&self, snapshot: &Snapshot) -> Vec<(IpNetwork, u32)> {
+        self.networks
+            .iter()
+            .map(|network| {
+                let score = snapshot
+                    .entries
+                    .range(network.network()..=network.broadcast())
+                    .map(|(_, score)| *score)
+                    .max()
+                    .unwrap_or_default();
+                (*network, score)
+            })
+            .collect()
+    }
+}
+
+/// A listed network while lossy rules are applied.
+#[derive(Debug, Clone, Copy)]
+struct Block {
+    start: u128,
+    prefix: u8,
+    listed: u128,
+}
+
+impl Block {
+    fn size(&self, ipv6: bool) -> u128 {
+        span(bits(ipv6) - self.prefix as u32).saturating_add(1)
+    }
+}
+
+/// Aggregation that lists a whole network once enough of its addresses are
+/// listed, e.g. a /24 with at least 32 listed hosts, accepting the other
+/// addresses as collateral.
+#[derive(Debug, Clone, Default)]
+pub struct Lossy {
+    v4: Vec<(u8, u128)>,
+    v6: Vec<(u8, u128)>,
+}
+
+impl Lossy {
+    /// Lossless until rules are added.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Lists an IPv4 network of `prefix` once `min_listed` of its addresses
+    /// are listed. Rules are applied from the longest prefix to the
+    /// shortest, so a /28 rule can feed a /24 rule.
+    pub fn ipv4(mut self, prefix: u8, min_listed: u128) -> Self {
+        self.v4.push((prefix.min(32), min_listed));
+        self.v4.sort_by_key(|(prefix, _)| Reverse(*prefix));
+        self
+    }
+
+    /// Lists an IPv6 network of `prefix` once `min_listed` of its addresses
+    /// are listed, see [`Lossy::ipv4`].
+    pub fn ipv6(mut self, prefix: u8, min_listed: u128) -> Self {
+        self.v6.push((prefix.min(128), min_listed));
+        self.v6.sort_by_key(|(prefix, _)| Reverse(*prefix));
+        self
+    }
+
+    fn apply(
+        rules: &[(u8, u128)],
+        networks: Vec<IpNetwork>,
+        ipv6: bool,
+        steps: &mut Vec<Step>,
+    ) -> Vec<IpNetwork> {
+        let mut blocks: Vec<Block> = networks
+            .iter()
+            .map(|network| {
+                let (start, _) = to_integer(network);
+                let mut block = Block {
+                    start,
+                    prefix: network.prefix(),
+                    listed: 0,
+                };
+                block.listed = block.size(ipv6);
+                block
+            })
+            .collect();
+
+        for (prefix, min_listed) in rules {
+            let mask = !span(bits(ipv6) - *prefix as u32);
+            let mut groups: BTreeMap<u128, Vec<Block>> = BTreeMap::new();
+            let mut kept = Vec::new();
+            for block in blocks {
+                if block.prefix > *prefix {
+                    groups.entry(block.start & mask).or_default().push(block);
+                } else {
+                    kept.push(block);
+                }
+            }
+
+            for (start, group) in groups {
+                let listed = group
+                    .iter()
+                    .fold(0u128, |total, b| total.saturating_add(b.listed));
+                if listed < (*min_listed).max(1) {
+                    kept.extend(group);
+                    continue;
+                }
+
+                let covered = group
+                    .iter()
+                    .fold(0u128, |total, b| total.saturating_add(b.size(ipv6)));
+                let block = Block {
+                    start,
+                    prefix: *prefix,
+                    listed,
+                };
+                let collateral = block.size(ipv6) - covered;
+                if collateral > 0 {
+                    steps.push(Step {
+                        network: to_network(start, *prefix, ipv6),
+                        listed,
+                        collateral,
+                    });
+                }
+                kept.push(block);
+            }
+            blocks = kept;
+        }
+
+        collapse(
+            blocks
+                .iter()
+                .map(|block| to_network(block.start, block.prefix, ipv6)),
+        )
+    }
+
+    pub fn aggregate<I: IntoIterator<Item = IpAddr>>(&self, addresses: I) -> Aggregation {
+        let (v4, v6): (Vec<_>, Vec<_>) = aggregate(addresses)
+            .into_iter()
+            .partition(|network| network.is_ipv4());
+
+        let mut steps = Vec::new();
+        let mut networks = Self::apply(&self.v4, v4, false, &mut steps);
+        networks.extend(Self::apply(&self.v6, v6, true, &mut steps));
+        Aggregation { networks, steps }
+    }
+
+    pub fn aggregate_blacklist(&self, response: &Response) -> Aggregation {
+        self.aggregate(response.entries().iter().map(|entry| entry.address()))
+    }
+
+    pub fn aggregate_snapshot(&self, snapshot: &Snapshot) -> Aggregation {
+        self.aggregate(snapshot.addresses())
+    }
+}
@@ -0,0 +1,210 @@
+use std::{
+    collections::HashMap,
+    fs,
+    net::IpAddr,
+    path::{Path, PathBuf},
+};
+
+use chrono::{DateTime, Duration, Utc};
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    endpoints::report::{Request, Response},
+    types::Category,
+    Client,
+};
+
+/// AbuseIPDB rejects repeat reports of the same IP from one account within
+/// this window.
+pub const REPORT_WINDOW: Duration = Duration::minutes(15);
+
+/// What to do with a repeat report that falls inside the window.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum Policy {
+    /// Drop the repeat.
+    Skip,
+    /// Drop the repeat, but remember categories that were not reported yet
+    /// and add them to the next report once the window has passed.
+    Merge,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct Entry {
+    reported_at: DateTime<Utc>,
+    categories: Vec<Category>,
+    #[serde(default)]
+    pending: Vec<Category>,
+}
+
+/// A report that was not sent because the same IP was reported recently.
+#[derive(Debug, Clone)]
+pub struct Skipped {
+    pub address: IpAddr,
+    pub last_reported_at: DateTime<Utc>,
+    /// The earliest time the IP can be reported again.
+    pub retry_at: DateTime<Utc>,
+    /// Categories that were held back for the next report, [`Policy::Merge`]
+    /// only.
+    pub merged: Vec<Category>,
+}
+
+/// The result of [`Deduplicator::report`].
+#[derive(Debug, Clone)]
+pub enum Outcome {
+    Sent(Response),
+    Skipped(Skipped),
+}
+
+/// Remembers recently reported IPs so that repeats within
+/// [`REPORT_WINDOW`] are suppressed before they reach the API.
+#[derive(Debug, Clone)]
+pub struct Deduplicator {
+    policy: Policy,
+    window: Duration,
+    entries: HashMap<IpAddr, Entry>,
+    path: Option<PathBuf>,
+}
+
+impl Deduplicator {
+    pub fn new(policy: Policy) -> Self {
+        Self {
+            policy,
+            window: REPORT_WINDOW,
+            entries: HashMap::new(),
+            path: None,
+        }
+    }
+
+    /// Loads the state from `path` if it exists and saves it back after
+    /// every change, so that restarts do not forget recent reports.
+    pub fn open<P: AsRef<Path>>(path: P, policy: Policy) -> crate::Result<Self> {
+        let path = path.as_ref().to_path_buf();
+        let entries = match fs::read(&path) {
+            Ok(data) => serde_json::from_slice(&data)?,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => HashMap::new(),
+            Err(e) => return Err(e.into()),
+        };
+
+        Ok(Self {
+            policy,
+            window: REPORT_WINDOW,
+            entries,
+            path: Some(path),
+        })
+    }
+
+    /// Overrides the window, e.g. for accounts with different limits.
+    pub fn with_window(mut self, window: Duration) -> Self {
+        self.window = window;
+        self
+    }
+
+    pub fn policy(&self) -> Policy {
+        self.policy
+    }
+
+    /// Writes the state to the file passed to [`Deduplicator::open`], if any.
+    pub fn save(&self) -> crate::Result<()> {
+        let Some(path) = &self.path else {
+            return Ok(());
+        };
+
+        let tmp = path.with_extension("tmp");
+        fs::write(&tmp, serde_json::to_vec(&self.entries)?)?;
+        fs::rename(tmp, path)?;
+        Ok(())
+    }
+
+    /// Forgets expired entries that have nothing pending.
+    pub fn prune(&mut self, now: DateTime<Utc>) {
+        let window = self.window;
+        self.entries
+            .retain(|_, entry| entry.reported_at + window > now || !entry.pending.is_empty());
+    }
+
+    /// Checks a request against the window without sending anything.
+    ///
+    /// Returns `Some` if the request has to be skipped. With
+    /// [`Policy::Merge`] its new categories are remembered for later.
+    pub fn check(&mut self, request: &Request, now: DateTime<Utc>) -> Option<Skipped> {
+        let window = self.window;
+        let policy = self.policy;
+        let entry = self.entries.get_mut(&request.address())?;
+
+        if entry.reported_at + window <= now {
+            return None;
+        }
+
+        if policy == Policy::Merge {
+            for category in request.categories() {
+                if !entry.categories.contains(category) && !entry.pending.contains(category) {
+                    entry.pending.push(*category);
+                }
+            }
+        }
+
+        Some(Skipped {
+            address: request.address(),
+            last_reported_at: entry.reported_at,
+            retry_at: entry.reported_at + window,
+            merged: entry.pending.clone(),
+        })
+    }
+
+    /// Records a successful report, clearing anything pending for the IP.
+    pub fn record(&mut self, request: &Request, now: DateTime<Utc>) {
+        self.entries.insert(
+            request.address(),
+            Entry {
+                reported_at: now,
+                categories: request.categories().to_vec(),
+                pending: Vec::new(),
+            },
+        );
+    }
+
+    /// Adds categories held back by [`Policy::Merge`] to a request that is
+    /// about to be sent.
+    pub fn merge_pending(&self, mut request: Request) -> Request {
+        if let Some(entry) = self.entries.get(&request.address) {
+            for category in &entry.pending {
+                if !request.categories.contains(category) {
+                    request.categories.push(*category);
+                }
+            }
+        }
+        request
+    }
+
+    /// Follow-up requests for IPs whose window has passed while merged
+    /// categories were still pending. Pass them to [`Deduplicator::report`].
+    pub fn ready(&self, now: DateTime<Utc>) -> Vec<Request> {
+        self.entries
+            .iter()
+            .filter(|(_, entry)| {
+                entry.reported_at + self.window <= now && !entry.pending.is_empty()
+            })
+            .map(|(address, entry)| Request::new(*address, entry.pending.clone()))
+            .collect()
+    }
+
+    /// Sends the request through [`Client::send_report`] unless the same IP
+    /// was reported within the window.
+    pub async fn report(&mut self, client: &Client, request: Request) -> crate::Result<Outcome> {
+        let now = Utc::now();
+        self.prune(now);
+
+        if let Some(skipped) = self.check(&request, now) {
+            self.save()?;
+            return Ok(Outcome::Skipped(skipped));
+        }
+
+        let request = self.merge_pending(request);
+        let response = client.send_report(&request).await?;
+
+        self.record(&request, now);
+        self.save()?;
+
+        Ok(Outcome::Sent(response))
+    }
+}
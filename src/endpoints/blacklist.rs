@@ -1,8 +1,12 @@
 use std::net::IpAddr;
 
+use chrono::{DateTime, Utc};
 use serde::{Deserialize, Serialize};
 
-use crate::{types::IpVersion, Client};
+use crate::{
+    types::{CountryCode, IpVersion},
+    Client,
+};
 
 fn serde_option_ip_version_to_i32<S>(
     version: &Option<IpVersion>,
@@ -39,6 +43,7 @@ pub struct Response {
 
 #[allow(dead_code)]
 #[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
 pub struct Meta {
     /// TODO: This is a string in the API, but it's actually a date
     generated_at: String,
@@ -46,12 +51,66 @@ pub struct Meta {
 
 #[allow(dead_code)]
 #[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
 pub struct Blacklist {
     #[serde(rename = "ipAddress")]
     address: IpAddr,
     abuse_confidence_score: u32,
     /// TODO: This is a string in the API, but it's actually a date
     last_reported_at: String,
+    /// Only present in verbose blacklists
+    #[serde(
+        default,
+        skip_serializing_if = "Option::is_none",
+        deserialize_with = "crate::country::serde_country_code_from_str"
+    )]
+    country_code: Option<CountryCode>,
+}
+
+fn parse_date(date: &str) -> Option<DateTime<Utc>> {
+    DateTime::parse_from_rfc3339(date).ok().map(|d| d.to_utc())
+}
+
+impl Response {
+    pub fn meta(&self) -> &Meta {
+        &self.meta
+    }
+
+    pub fn entries(&self) -> &[Blacklist] {
+        &self.entries
+    }
+}
+
+impl Meta {
+    pub fn generated_at(&self) -> Option<DateTime<Utc>> {
+        parse_date(&self.generated_at)
+    }
+}
+
+impl Blacklist {
+    pub fn address(&self) -> IpAddr {
+        self.address
+    }
+
+    pub fn abuse_confidence_score(&self) -> u32 {
+        self.abuse_confidence_score
+    }
+
+    pub fn last_reported_at(&self) -> Option<DateTime<Utc>> {
+        parse_date(&self.last_reported_at)
+    }
+
+    pub fn country_code(&self) -> Option<CountryCode> {
+        self.country_code
+    }
+}
+
+fn join_countries(countries: Vec<CountryCode>) -> String {
+    countries
+        .iter()
+        .map(CountryCode::as_str)
+        .collect::<Vec<_>>()
+        .join(",")
 }
 
 impl Client {
@@ -59,15 +118,15 @@ impl Client {
         &self,
         confidence_minimum: u32,
         limit: Option<u32>,
-        only_countries: Option<Vec<String>>,
-        except_countries: Option<Vec<String>>,
+        only_countries: Option<Vec<CountryCode>>,
+        except_countries: Option<Vec<CountryCode>>,
         ip_version: Option<IpVersion>,
     ) -> crate::Result<Response> {
         let request = Request {
             confidence_minimum,
             limit,
-            only_countries: only_countries.map(|v| v.join(",")),
-            except_countries: except_countries.map(|v| v.join(",")),
+            only_countries: only_countries.map(join_countries),
+            except_countries: except_countries.map(join_countries),
             ip_version,
         };
 
@@ -0,0 +1,91 @@
+use chrono::Utc;
+use reqwest::multipart::{Form, Part};
+use serde::{Deserialize, Serialize};
+
+use crate::{endpoints::report::Request, types::DataWrapper, Client};
+
+/// The API accepts at most this many rows per upload.
+pub const MAX_ROWS: usize = 10_000;
+
+#[allow(dead_code)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Response {
+    saved_reports: u32,
+    invalid_reports: Vec<InvalidReport>,
+}
+
+impl Response {
+    pub fn saved_reports(&self) -> u32 {
+        self.saved_reports
+    }
+
+    pub fn invalid_reports(&self) -> &[InvalidReport] {
+        &self.invalid_reports
+    }
+}
+
+#[allow(dead_code)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct InvalidReport {
+    error: String,
+    input: String,
+    row_number: u32,
+}
+
+impl InvalidReport {
+    pub fn error(&self) -> &str {
+        &self.error
+    }
+
+    pub fn row_number(&self) -> u32 {
+        self.row_number
+    }
+}
+
+fn csv_field(value: &str) -> String {
+    let value = value.replace(['\r', '\n'], " ");
+    format!("\"{}\"", value.replace('"', "\"\""))
+}
+
+/// Renders requests in the CSV format expected by `bulk-report`. Requests
+/// without a timestamp are dated now.
+pub fn to_csv(requests: &[Request]) -> String {
+    let mut csv = String::from("IP,Categories,ReportDate,Comment\n");
+    for request in requests {
+        let categories = request
+            .categories()
+            .iter()
+            .map(|c| (*c as u8).to_string())
+            .collect::<Vec<_>>()
+            .join(",");
+        let date = request.timestamp().unwrap_or_else(Utc::now).to_rfc3339();
+
+        csv.push_str(&request.address().to_string());
+        csv.push(',');
+        csv.push_str(&csv_field(&categories));
+        csv.push(',');
+        csv.push_str(&date);
+        csv.push(',');
+        csv.push_str(&csv_field(request.comment().unwrap_or_default()));
+        csv.push('\n');
+    }
+    csv
+}
+
+impl Client {
+    /// Uploads up to [`MAX_ROWS`] reports in a single request.
+    pub async fn bulk_report(&self, requests: &[Request]) -> crate::Result<Response> {
+        let part = Part::text(to_csv(requests))
+            .file_name("report.csv")
+            .mime_str("text/csv")?;
+        let form = Form::new().part("csv", part);
+
+        let data: DataWrapper<Response> = self
+            .post_multipart(self.base.join("bulk-report").unwrap(), form)
+            .await?;
+
+        Ok(data.data)
+    }
+}
@@ -4,7 +4,7 @@ use chrono::Duration;
 use serde::{Deserialize, Serialize};
 
 use crate::{
-    types::{DataWrapper, IpVersion, Report, UsageType},
+    types::{CountryCode, DataWrapper, IpVersion, Report, UsageType},
     Client,
 };
 
@@ -33,7 +33,11 @@ pub struct Response {
     /// Only present if a whitelist lookup was performed
     is_whitelisted: Option<bool>,
     abuse_confidence_score: i32,
-    country_code: Option<String>,
+    #[serde(
+        default,
+        deserialize_with = "crate::country::serde_country_code_from_str"
+    )]
+    country_code: Option<CountryCode>,
     country_name: Option<String>,
     #[serde(deserialize_with = "crate::types::serde_usage_type_from_str")]
     usage_type: Vec<UsageType>,
@@ -48,6 +52,12 @@ pub struct Response {
     reports: Option<Vec<Report>>,
 }
 
+impl Response {
+    pub fn country_code(&self) -> Option<CountryCode> {
+        self.country_code
+    }
+}
+
 impl Client {
     pub async fn check(
         &self,
@@ -55,8 +65,6 @@ impl Client {
         max_age: Option<Duration>,
         verbose: Option<bool>,
     ) -> crate::Result<Response> {
-        let max_age = max_age.map(Into::into);
-
         let request = Request {
             address,
             max_age,
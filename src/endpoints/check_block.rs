@@ -41,8 +41,6 @@ impl Client {
         network: IpNetwork,
         max_age: Option<Duration>,
     ) -> crate::Result<Response> {
-        let max_age = max_age.map(Into::into);
-
         let request = Request { network, max_age };
 
         let data: DataWrapper<Response> = self
@@ -1,4 +1,5 @@
 pub mod blacklist;
+pub mod bulk_report;
 pub mod check;
 pub mod check_block;
 pub mod report;
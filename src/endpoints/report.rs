@@ -18,20 +18,58 @@ where
 }
 
 use crate::{
+    sanitize::Sanitizer,
     types::{Category, DataWrapper},
     Client,
 };
 
 #[derive(Debug, Clone, Serialize)]
 #[serde(rename_all = "camelCase")]
-pub struct Request<'a> {
+pub struct Request {
     #[serde(rename = "ip")]
-    address: &'a IpAddr,
+    pub(crate) address: IpAddr,
     #[serde(serialize_with = "crate::types::serde_categories_to_string")]
-    categories: &'a [Category],
-    comment: Option<&'a str>,
+    pub(crate) categories: Vec<Category>,
+    pub(crate) comment: Option<String>,
     #[serde(serialize_with = "serde_option_datetime_to_string")]
-    timestamp: Option<DateTime<Utc>>,
+    pub(crate) timestamp: Option<DateTime<Utc>>,
+}
+
+impl Request {
+    pub fn new(address: IpAddr, categories: Vec<Category>) -> Self {
+        Self {
+            address,
+            categories,
+            comment: None,
+            timestamp: None,
+        }
+    }
+
+    pub fn with_comment<T: ToString>(mut self, comment: T) -> Self {
+        self.comment = Some(comment.to_string());
+        self
+    }
+
+    pub fn with_timestamp(mut self, timestamp: DateTime<Utc>) -> Self {
+        self.timestamp = Some(timestamp);
+        self
+    }
+
+    pub fn address(&self) -> IpAddr {
+        self.address
+    }
+
+    pub fn categories(&self) -> &[Category] {
+        &self.categories
+    }
+
+    pub fn comment(&self) -> Option<&str> {
+        self.comment.as_deref()
+    }
+
+    pub fn timestamp(&self) -> Option<DateTime<Utc>> {
+        self.timestamp
+    }
 }
 
 #[allow(dead_code)]
@@ -43,6 +81,93 @@ pub struct Response {
     abuse_confidence_score: u32,
 }
 
+impl Response {
+    pub fn address(&self) -> IpAddr {
+        self.address
+    }
+
+    pub fn abuse_confidence_score(&self) -> u32 {
+        self.abuse_confidence_score
+    }
+}
+
+/// The result of [`Builder::send`].
+#[derive(Debug, Clone)]
+pub enum Outcome {
+    /// The report was submitted.
+    Sent(Response),
+    /// Dry-run mode: nothing was submitted, this is what would have been.
+    DryRun(Request),
+}
+
+/// Builds a report whose comment is run through a [`Sanitizer`] before
+/// it is sent.
+pub struct Builder<'a> {
+    client: &'a Client,
+    request: Request,
+    sanitizer: Sanitizer,
+    dry_run: bool,
+}
+
+impl<'a> Builder<'a> {
+    pub fn category(mut self, category: Category) -> Self {
+        if !self.request.categories.contains(&category) {
+            self.request.categories.push(category);
+        }
+        self
+    }
+
+    pub fn categories(mut self, categories: &[Category]) -> Self {
+        for category in categories {
+            self = self.category(*category);
+        }
+        self
+    }
+
+    pub fn comment<T: ToString>(mut self, comment: T) -> Self {
+        self.request.comment = Some(comment.to_string());
+        self
+    }
+
+    pub fn timestamp(mut self, timestamp: DateTime<Utc>) -> Self {
+        self.request.timestamp = Some(timestamp);
+        self
+    }
+
+    /// Replaces the default sanitizer.
+    pub fn sanitizer(mut self, sanitizer: Sanitizer) -> Self {
+        self.sanitizer = sanitizer;
+        self
+    }
+
+    /// When enabled, [`Builder::send`] returns the final request instead of
+    /// submitting it.
+    pub fn dry_run(mut self, dry_run: bool) -> Self {
+        self.dry_run = dry_run;
+        self
+    }
+
+    /// Returns the request as it would be sent, with the comment sanitized.
+    pub fn build(&self) -> Request {
+        let mut request = self.request.clone();
+        request.comment = request
+            .comment
+            .map(|comment| self.sanitizer.sanitize(&comment))
+            .filter(|comment| !comment.is_empty());
+        request
+    }
+
+    pub async fn send(self) -> crate::Result<Outcome> {
+        let request = self.build();
+
+        if self.dry_run {
+            return Ok(Outcome::DryRun(request));
+        }
+
+        Ok(Outcome::Sent(self.client.send_report(&request).await?))
+    }
+}
+
 impl Client {
     pub async fn report(
         &self,
@@ -52,16 +177,32 @@ impl Client {
         timestamp: Option<DateTime<Utc>>,
     ) -> crate::Result<Response> {
         let request = Request {
-            address,
-            categories,
-            comment,
+            address: *address,
+            categories: categories.to_vec(),
+            comment: comment.map(ToString::to_string),
             timestamp,
         };
 
+        self.send_report(&request).await
+    }
+
+    /// Sends a prepared request as-is, without sanitizing its comment.
+    pub async fn send_report(&self, request: &Request) -> crate::Result<Response> {
         let data: DataWrapper<Response> = self
             .post(self.base.join("report").unwrap(), request)
             .await?;
 
         Ok(data.data)
     }
+
+    /// Starts a report whose comment is sanitized with the default
+    /// [`Sanitizer`] unless another one is configured.
+    pub fn report_builder(&self, address: IpAddr) -> Builder<'_> {
+        Builder {
+            client: self,
+            request: Request::new(address, Vec::new()),
+            sanitizer: Sanitizer::default(),
+            dry_run: false,
+        }
+    }
 }
@@ -45,8 +45,6 @@ impl Client {
         page: Option<u32>,
         per_page: Option<u32>,
     ) -> crate::Result<Response> {
-        let max_age = max_age.map(Into::into);
-
         let request = Request {
             address,
             max_age,
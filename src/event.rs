@@ -0,0 +1,70 @@
+use std::net::IpAddr;
+
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    endpoints::report::Request,
+    sanitize::{truncate, MAX_COMMENT_LENGTH},
+    types::Category,
+};
+
+/// What an event was aimed at, e.g. the user of a failed login or the port
+/// of a probe. Used to summarize many events in a single comment.
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub struct Target {
+    /// Singular noun, e.g. `"user"`.
+    pub noun: String,
+    pub value: String,
+}
+
+/// A single observation of abuse by an IP, as produced by the log parsers
+/// and listeners in this crate.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Event {
+    pub address: IpAddr,
+    pub categories: Vec<Category>,
+    /// Singular description, e.g. `"failed SSH login"`.
+    pub kind: String,
+    pub target: Option<Target>,
+    /// The (scrubbed) log line or payload the event was derived from.
+    pub evidence: String,
+    pub timestamp: DateTime<Utc>,
+}
+
+impl Event {
+    pub fn new<TK: ToString, TE: ToString>(
+        address: IpAddr,
+        categories: &[Category],
+        kind: TK,
+        evidence: TE,
+        timestamp: DateTime<Utc>,
+    ) -> Self {
+        Self {
+            address,
+            categories: categories.to_vec(),
+            kind: kind.to_string(),
+            target: None,
+            evidence: evidence.to_string(),
+            timestamp,
+        }
+    }
+
+    pub fn with_target<TN: ToString, TV: ToString>(mut self, noun: TN, value: TV) -> Self {
+        self.target = Some(Target {
+            noun: noun.to_string(),
+            value: value.to_string(),
+        });
+        self
+    }
+
+    /// A report for this event alone, with the evidence as comment.
+    pub fn to_request(&self) -> Request {
+        let mut request =
+            Request::new(self.address, self.categories.clone()).with_timestamp(self.timestamp);
+        if !self.evidence.is_empty() {
+            request = request.with_comment(truncate(&self.evidence, MAX_COMMENT_LENGTH));
+        }
+        request
+    }
+}
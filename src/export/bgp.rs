@@ -0,0 +1,145 @@
+use std::{collections::BTreeSet, fmt::Write};
+
+use ipnetwork::IpNetwork;
+
+use crate::{
+    cidr::aggregate,
+    export::{describe, host_networks},
+    snapshot::Snapshot,
+};
+
+/// The well-known BLACKHOLE community of RFC 7999.
+pub const BLACKHOLE: (u16, u16) = (65535, 666);
+/// The well-known NO_EXPORT community, commonly added to blackhole routes
+/// so they stay within the neighbouring AS.
+pub const NO_EXPORT: (u16, u16) = (65535, 65281);
+
+/// Renders snapshots as blackhole routes for BIRD and ExaBGP: a host route
+/// per listed address, or aggregated prefixes, tagged with BGP communities.
+#[derive(Debug, Clone)]
+pub struct Blackhole {
+    communities: Vec<(u16, u16)>,
+    next_hop: String,
+    aggregate: bool,
+}
+
+impl Default for Blackhole {
+    fn default() -> Self {
+        Self {
+            communities: vec![BLACKHOLE],
+            next_hop: "self".to_string(),
+            aggregate: false,
+        }
+    }
+}
+
+impl Blackhole {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// The communities of every route, default to [`BLACKHOLE`].
+    pub fn communities(mut self, communities: &[(u16, u16)]) -> Self {
+        self.communities = communities.to_vec();
+        self
+    }
+
+    /// The ExaBGP next hop, defaults to `self`. Point it at an address that
+    /// is routed to a discard interface on the receiving routers.
+    pub fn next_hop<T: ToString>(mut self, next_hop: T) -> Self {
+        self.next_hop = next_hop.to_string();
+        self
+    }
+
+    /// Announce aggregated prefixes instead of /32 and /128 routes. Off by
+    /// default.
+    pub fn aggregate(mut self, aggregate: bool) -> Self {
+        self.aggregate = aggregate;
+        self
+    }
+
+    fn networks(&self, snapshot: &Snapshot) -> Vec<IpNetwork> {
+        if self.aggregate {
+            aggregate(snapshot.addresses())
+        } else {
+            host_networks(snapshot.addresses())
+        }
+    }
+
+    fn render_bird(&self, snapshot: &Snapshot, ipv6: bool) -> String {
+        let communities = self
+            .communities
+            .iter()
+            .map(|(asn, value)| format!(" bgp_community.add(({asn}, {value}));"))
+            .collect::<String>();
+
+        let mut config = format!("# {}\n", describe(snapshot));
+        for network in self.networks(snapshot) {
+            if network.is_ipv6() == ipv6 {
+                writeln!(config, "route {network} blackhole {{{communities} }};").unwrap();
+            }
+        }
+        config
+    }
+
+    /// Static routes for the IPv4 channel of a BIRD 2 static protocol:
+    ///
+    /// ```text
+    /// protocol static abuseipdb_v4 {
+    ///     ipv4;
+    ///     include "/etc/bird/abuseipdb_v4.conf";
+    /// }
+    /// ```
+    ///
+    /// BIRD compares the routes on `birdc configure` and only announces
+    /// what changed, so the whole list is rendered every time.
+    pub fn render_bird_v4(&self, snapshot: &Snapshot) -> String {
+        self.render_bird(snapshot, false)
+    }
+
+    /// Static routes for the IPv6 channel of a BIRD 2 static protocol, see
+    /// [`Blackhole::render_bird_v4`].
+    pub fn render_bird_v6(&self, snapshot: &Snapshot) -> String {
+        self.render_bird(snapshot, true)
+    }
+
+    fn exabgp_line(&self, verb: &str, network: &IpNetwork) -> String {
+        let mut line = format!("{verb} route {network} next-hop {}", self.next_hop);
+        if verb == "announce" && !self.communities.is_empty() {
+            let communities = self
+                .communities
+                .iter()
+                .map(|(asn, value)| format!("{asn}:{value}"))
+                .collect::<Vec<_>>()
+                .join(" ");
+            write!(line, " community [{communities}]").unwrap();
+        }
+        line.push('\n');
+        line
+    }
+
+    /// ExaBGP API commands announcing every route, for when the process
+    /// (and with it the BGP session state) starts.
+    pub fn render_exabgp(&self, snapshot: &Snapshot) -> String {
+        self.networks(snapshot)
+            .iter()
+            .map(|network| self.exabgp_line("announce", network))
+            .collect()
+    }
+
+    /// ExaBGP API commands withdrawing the routes of `previous` that are
+    /// gone and announcing those that are new in `current`. Keep the last
+    /// applied snapshot with [`Snapshot::save`] to diff against it.
+    pub fn render_exabgp_delta(&self, previous: &Snapshot, current: &Snapshot) -> String {
+        let before: BTreeSet<IpNetwork> = self.networks(previous).into_iter().collect();
+        let after: BTreeSet<IpNetwork> = self.networks(current).into_iter().collect();
+
+        let withdrawn = before
+            .difference(&after)
+            .map(|network| self.exabgp_line("withdraw", network));
+        let announced = after
+            .difference(&before)
+            .map(|network| self.exabgp_line("announce", network));
+        withdrawn.chain(announced).collect()
+    }
+}
@@ -0,0 +1,164 @@
+use std::fmt::Write;
+
+use ipnetwork::IpNetwork;
+
+use crate::{
+    export::{describe, fit, format_network},
+    snapshot::Snapshot,
+};
+
+/// The platforms [`DeviceList`] renders for.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Device {
+    /// An IOS `object-group network`. IOS groups hold IPv4 only, so IPv6
+    /// addresses are left out.
+    CiscoIos,
+    /// An ASA `object-group network` with `network-object` lines.
+    CiscoAsa,
+    /// Junos `set` commands replacing a `policy-options prefix-list`, for
+    /// `load set`.
+    Juniper,
+    /// A RouterOS script replacing an IPv4 and an IPv6 address list.
+    Mikrotik,
+    /// A pf table file, for `pfctl -t <table> -T replace -f <file>`.
+    Pf,
+}
+
+impl Device {
+    /// pf refuses tables beyond its `table-entries` limit, 200000 by
+    /// default. The others are limited by memory only.
+    fn default_max_entries(&self) -> Option<usize> {
+        match self {
+            Device::Pf => Some(200_000),
+            _ => None,
+        }
+    }
+
+    fn comment(&self) -> &'static str {
+        match self {
+            Device::CiscoIos | Device::CiscoAsa => "!",
+            Device::Juniper | Device::Mikrotik | Device::Pf => "#",
+        }
+    }
+}
+
+/// Renders snapshots as address lists for routers and firewalls.
+///
+/// Addresses are always aggregated into CIDRs. If a list is still longer
+/// than the device allows, the lowest scored addresses are left out.
+#[derive(Debug, Clone)]
+pub struct DeviceList {
+    device: Device,
+    name: String,
+    max_entries: Option<usize>,
+}
+
+impl DeviceList {
+    pub fn new(device: Device) -> Self {
+        Self {
+            device,
+            name: "abuseipdb".to_string(),
+            max_entries: device.default_max_entries(),
+        }
+    }
+
+    /// The name of the group, prefix list, address list or table, defaults
+    /// to `abuseipdb`.
+    pub fn name<T: ToString>(mut self, name: T) -> Self {
+        self.name = name.to_string();
+        self
+    }
+
+    /// The most prefixes the device takes.
+    pub fn max_entries(mut self, max_entries: usize) -> Self {
+        self.max_entries = Some(max_entries);
+        self
+    }
+
+    fn networks(&self, snapshot: &Snapshot) -> (Vec<IpNetwork>, usize) {
+        let ipv4_only;
+        let snapshot = if self.device == Device::CiscoIos {
+            ipv4_only = Snapshot {
+                generated_at: snapshot.generated_at,
+                entries: snapshot
+                    .entries
+                    .iter()
+                    .filter(|(address, _)| address.is_ipv4())
+                    .map(|(address, score)| (*address, *score))
+                    .collect(),
+            };
+            &ipv4_only
+        } else {
+            snapshot
+        };
+        fit(snapshot, self.max_entries.unwrap_or(usize::MAX))
+    }
+
+    pub fn render(&self, snapshot: &Snapshot) -> String {
+        let (networks, dropped) = self.networks(snapshot);
+        let name = &self.name;
+        let comment = self.device.comment();
+
+        let mut config = format!("{comment} {}\n", describe(snapshot));
+        if dropped > 0 {
+            writeln!(
+                config,
+                "{comment} {dropped} lowest scored addresses left out to fit {} entries",
+                networks.len()
+            )
+            .unwrap();
+        }
+
+        match self.device {
+            Device::CiscoIos | Device::CiscoAsa => {
+                let indent = if self.device == Device::CiscoAsa {
+                    " network-object "
+                } else {
+                    " "
+                };
+                writeln!(config, "object-group network {name}").unwrap();
+                for network in &networks {
+                    match network {
+                        IpNetwork::V4(n) if n.prefix() == 32 => {
+                            writeln!(config, "{indent}host {}", n.ip())
+                        }
+                        IpNetwork::V4(n) => writeln!(config, "{indent}{} {}", n.ip(), n.mask()),
+                        IpNetwork::V6(n) if n.prefix() == 128 => {
+                            writeln!(config, "{indent}host {}", n.ip())
+                        }
+                        IpNetwork::V6(n) => writeln!(config, "{indent}{n}"),
+                    }
+                    .unwrap();
+                }
+                config.push_str("exit\n");
+            }
+            Device::Juniper => {
+                writeln!(config, "delete policy-options prefix-list {name}").unwrap();
+                for network in &networks {
+                    writeln!(config, "set policy-options prefix-list {name} {network}").unwrap();
+                }
+            }
+            Device::Mikrotik => {
+                let (v4, v6): (Vec<_>, Vec<_>) = networks.iter().partition(|n| n.is_ipv4());
+                for (menu, networks) in [("/ip", v4), ("/ipv6", v6)] {
+                    writeln!(config, "{menu} firewall address-list").unwrap();
+                    writeln!(config, "remove [find list={name}]").unwrap();
+                    for network in networks {
+                        writeln!(
+                            config,
+                            "add list={name} address={}",
+                            format_network(network)
+                        )
+                        .unwrap();
+                    }
+                }
+            }
+            Device::Pf => {
+                for network in &networks {
+                    writeln!(config, "{}", format_network(network)).unwrap();
+                }
+            }
+        }
+        config
+    }
+}
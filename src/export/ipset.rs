@@ -0,0 +1,111 @@
+use std::fmt::Write;
+
+use ipnetwork::IpNetwork;
+
+use crate::{
+    cidr::aggregate,
+    export::{format_network, host_networks},
+    snapshot::Snapshot,
+};
+
+/// The `maxelem` ipset uses when none is given.
+const DEFAULT_MAXELEM: usize = 65536;
+
+/// Renders snapshots as `ipset restore` input that fills a temporary set
+/// per family and swaps it with the live one, so iptables rules matching
+/// the live sets never see them empty. Run it with `ipset -exist restore`:
+/// each temporary set is destroyed first, in case an interrupted run left
+/// it behind, and `-exist` keeps that from failing when it is missing.
+///
+/// The live sets are only created when missing, so their `create` lines
+/// carry no `maxelem`. The temporary sets get the entry count rounded up
+/// to a power of two, at least 65536, and take it to the live name on
+/// `swap`, which only checks type and family.
+#[derive(Debug, Clone)]
+pub struct Ipset {
+    set_v4: String,
+    set_v6: String,
+    aggregate: bool,
+    maxelem: Option<usize>,
+}
+
+impl Default for Ipset {
+    fn default() -> Self {
+        Self {
+            set_v4: "abuseipdb_v4".to_string(),
+            set_v6: "abuseipdb_v6".to_string(),
+            aggregate: false,
+            maxelem: None,
+        }
+    }
+}
+
+impl Ipset {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// The set names, default to `abuseipdb_v4` and `abuseipdb_v6`. ipset
+    /// limits names to 31 characters, including the `-new` suffix of the
+    /// temporary sets.
+    pub fn sets<T4: ToString, T6: ToString>(mut self, v4: T4, v6: T6) -> Self {
+        self.set_v4 = v4.to_string();
+        self.set_v6 = v6.to_string();
+        self
+    }
+
+    /// Merge adjacent addresses into CIDRs in `hash:net` sets instead of
+    /// listing single addresses in `hash:ip` sets. Off by default.
+    pub fn aggregate(mut self, aggregate: bool) -> Self {
+        self.aggregate = aggregate;
+        self
+    }
+
+    /// A fixed `maxelem` instead of one derived from the entry count.
+    pub fn maxelem(mut self, maxelem: usize) -> Self {
+        self.maxelem = Some(maxelem);
+        self
+    }
+
+    fn render_set(&self, script: &mut String, name: &str, family: &str, networks: &[&IpNetwork]) {
+        let kind = if self.aggregate {
+            "hash:net"
+        } else {
+            "hash:ip"
+        };
+        let maxelem = self
+            .maxelem
+            .unwrap_or_else(|| networks.len().next_power_of_two().max(DEFAULT_MAXELEM));
+        let tmp = format!("{name}-new");
+
+        writeln!(script, "create {name} {kind} family {family} -exist").unwrap();
+        writeln!(script, "destroy {tmp}").unwrap();
+        writeln!(
+            script,
+            "create {tmp} {kind} family {family} maxelem {maxelem}"
+        )
+        .unwrap();
+        writeln!(script, "flush {tmp}").unwrap();
+        for network in networks {
+            writeln!(script, "add {tmp} {}", format_network(network)).unwrap();
+        }
+        writeln!(script, "swap {tmp} {name}").unwrap();
+        writeln!(script, "destroy {tmp}").unwrap();
+    }
+
+    /// Input for `ipset -exist restore` replacing the contents of both sets
+    /// with the snapshot.
+    pub fn render(&self, snapshot: &Snapshot) -> String {
+        let networks = if self.aggregate {
+            aggregate(snapshot.addresses())
+        } else {
+            host_networks(snapshot.addresses())
+        };
+        let (v4, v6): (Vec<_>, Vec<_>) = networks.iter().partition(|n| n.is_ipv4());
+
+        let mut script = String::new();
+        self.render_set(&mut script, &self.set_v4, "inet", &v4);
+        self.render_set(&mut script, &self.set_v6, "inet6", &v6);
+        script
+    }
+}
@@ -0,0 +1,68 @@
+use std::net::IpAddr;
+
+use ipnetwork::IpNetwork;
+
+use crate::{cidr::aggregate, snapshot::Snapshot};
+
+pub mod bgp;
+pub mod device;
+pub mod ipset;
+pub mod nftables;
+pub mod rpz;
+pub mod web;
+
+/// Single addresses as host prefixes, for exporters that do not aggregate.
+pub(crate) fn host_networks<I: IntoIterator<Item = IpAddr>>(addresses: I) -> Vec<IpNetwork> {
+    let mut networks: Vec<IpNetwork> = addresses.into_iter().map(IpNetwork::from).collect();
+    networks.sort_by_key(|n| (n.is_ipv6(), n.ip()));
+    networks
+}
+
+/// Aggregated networks of at most `max_entries` prefixes. When even the
+/// aggregate is too long, the lowest scored addresses are left out. Returns
+/// the networks and the number of addresses left out.
+pub(crate) fn fit(snapshot: &Snapshot, max_entries: usize) -> (Vec<IpNetwork>, usize) {
+    let networks = aggregate(snapshot.addresses());
+    if networks.len() <= max_entries {
+        return (networks, 0);
+    }
+
+    let mut ranked: Vec<(IpAddr, u32)> = snapshot.entries.iter().map(|(a, s)| (*a, *s)).collect();
+    ranked.sort_by(|a, b| b.1.cmp(&a.1).then(a.0.cmp(&b.0)));
+    let top = |count: usize| aggregate(ranked[..count].iter().map(|(address, _)| *address));
+
+    // the most addresses whose aggregate fits, at least `max_entries`
+    let (mut low, mut high) = (max_entries.min(ranked.len()), ranked.len());
+    while low < high {
+        let middle = (low + high).div_ceil(2);
+        if top(middle).len() <= max_entries {
+            low = middle;
+        } else {
+            high = middle - 1;
+        }
+    }
+    (top(low), ranked.len() - low)
+}
+
+/// A network as an address when it is a single host, e.g. `192.0.2.1`
+/// rather than `192.0.2.1/32`.
+pub(crate) fn format_network(network: &IpNetwork) -> String {
+    let host_prefix = if network.is_ipv4() { 32 } else { 128 };
+    if network.prefix() == host_prefix {
+        network.ip().to_string()
+    } else {
+        network.to_string()
+    }
+}
+
+/// A one-line description of a snapshot for the header of an export.
+pub(crate) fn describe(snapshot: &Snapshot) -> String {
+    match snapshot.generated_at {
+        Some(generated_at) => format!(
+            "AbuseIPDB blacklist generated at {}, {} addresses",
+            generated_at.to_rfc3339(),
+            snapshot.len()
+        ),
+        None => format!("AbuseIPDB blacklist, {} addresses", snapshot.len()),
+    }
+}
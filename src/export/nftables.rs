@@ -0,0 +1,142 @@
+use std::{collections::BTreeSet, fmt::Write};
+
+use ipnetwork::IpNetwork;
+
+use crate::{
+    cidr::aggregate,
+    export::{describe, format_network, host_networks},
+    snapshot::Snapshot,
+};
+
+/// Renders snapshots as `nft -f` scripts that keep an IPv4 and an IPv6
+/// interval set in sync with the blacklist.
+///
+/// `nft -f` applies a script as one transaction, so the sets never appear
+/// empty or half filled to the ruleset. Reference them from your own
+/// chains, e.g. `ip saddr @abuseipdb_v4 drop`.
+#[derive(Debug, Clone)]
+pub struct Nftables {
+    family: String,
+    table: String,
+    set_v4: String,
+    set_v6: String,
+    aggregate: bool,
+}
+
+impl Default for Nftables {
+    fn default() -> Self {
+        Self {
+            family: "inet".to_string(),
+            table: "abuseipdb".to_string(),
+            set_v4: "abuseipdb_v4".to_string(),
+            set_v6: "abuseipdb_v6".to_string(),
+            aggregate: true,
+        }
+    }
+}
+
+impl Nftables {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// The table holding the sets, defaults to `inet abuseipdb`.
+    pub fn table<TF: ToString, TN: ToString>(mut self, family: TF, name: TN) -> Self {
+        self.family = family.to_string();
+        self.table = name.to_string();
+        self
+    }
+
+    /// The set names, default to `abuseipdb_v4` and `abuseipdb_v6`.
+    pub fn sets<T4: ToString, T6: ToString>(mut self, v4: T4, v6: T6) -> Self {
+        self.set_v4 = v4.to_string();
+        self.set_v6 = v6.to_string();
+        self
+    }
+
+    /// Merge adjacent addresses into prefixes, on by default.
+    pub fn aggregate(mut self, aggregate: bool) -> Self {
+        self.aggregate = aggregate;
+        self
+    }
+
+    fn networks(&self, snapshot: &Snapshot) -> Vec<IpNetwork> {
+        if self.aggregate {
+            aggregate(snapshot.addresses())
+        } else {
+            host_networks(snapshot.addresses())
+        }
+    }
+
+    fn header(&self, script: &mut String, snapshot: &Snapshot) {
+        script.push_str("#!/usr/sbin/nft -f\n");
+        writeln!(script, "# {}", describe(snapshot)).unwrap();
+
+        writeln!(script, "\ntable {} {} {{", self.family, self.table).unwrap();
+        writeln!(
+            script,
+            "\tset {} {{\n\t\ttype ipv4_addr\n\t\tflags interval\n\t}}",
+            self.set_v4
+        )
+        .unwrap();
+        writeln!(
+            script,
+            "\tset {} {{\n\t\ttype ipv6_addr\n\t\tflags interval\n\t}}",
+            self.set_v6
+        )
+        .unwrap();
+        script.push_str("}\n\n");
+    }
+
+    /// Writes `add element` or `delete element` statements for both sets,
+    /// leaving out empty ones, which nft rejects.
+    fn elements<'a, I>(&self, script: &mut String, verb: &str, networks: I)
+    where
+        I: IntoIterator<Item = &'a IpNetwork>,
+    {
+        let (v4, v6): (Vec<_>, Vec<_>) = networks.into_iter().partition(|n| n.is_ipv4());
+        for (set, networks) in [(&self.set_v4, v4), (&self.set_v6, v6)] {
+            if networks.is_empty() {
+                continue;
+            }
+            writeln!(
+                script,
+                "{verb} element {} {} {set} {{",
+                self.family, self.table
+            )
+            .unwrap();
+            for network in networks {
+                writeln!(script, "\t{},", format_network(network)).unwrap();
+            }
+            script.push_str("}\n");
+        }
+    }
+
+    /// A script replacing the contents of both sets with the snapshot.
+    pub fn render(&self, snapshot: &Snapshot) -> String {
+        let mut script = String::new();
+        self.header(&mut script, snapshot);
+        writeln!(
+            script,
+            "flush set {} {} {}\nflush set {} {} {}",
+            self.family, self.table, self.set_v4, self.family, self.table, self.set_v6
+        )
+        .unwrap();
+        self.elements(&mut script, "add", &self.networks(snapshot));
+        script
+    }
+
+    /// A script changing sets filled from `previous` (by [`Nftables::render`]
+    /// or an earlier delta) into `current`, deleting and adding only the
+    /// prefixes that differ. Both must be rendered with the same options.
+    pub fn render_delta(&self, previous: &Snapshot, current: &Snapshot) -> String {
+        let before: BTreeSet<IpNetwork> = self.networks(previous).into_iter().collect();
+        let after: BTreeSet<IpNetwork> = self.networks(current).into_iter().collect();
+
+        let mut script = String::new();
+        self.header(&mut script, current);
+        self.elements(&mut script, "delete", before.difference(&after));
+        self.elements(&mut script, "add", after.difference(&before));
+        script
+    }
+}
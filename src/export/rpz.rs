@@ -0,0 +1,157 @@
+use std::{fmt::Write, net::IpAddr};
+
+use chrono::Utc;
+use ipnetwork::IpNetwork;
+
+use crate::{
+    cidr::aggregate,
+    export::{describe, host_networks},
+    snapshot::Snapshot,
+};
+
+/// What the resolver answers for names resolving into a listed network.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Action {
+    /// The name does not exist (`CNAME .`).
+    Nxdomain,
+    /// The name exists without records (`CNAME *.`).
+    Nodata,
+    /// No answer at all (`CNAME rpz-drop.`).
+    Drop,
+}
+
+impl Action {
+    fn target(&self) -> &'static str {
+        match self {
+            Action::Nxdomain => ".",
+            Action::Nodata => "*.",
+            Action::Drop => "rpz-drop.",
+        }
+    }
+}
+
+/// The owner name of an `rpz-ip` trigger, e.g. `24.0.2.0.192.rpz-ip` for
+/// `192.0.2.0/24` and `32.zz.db8.2001.rpz-ip` for `2001:db8::/32`.
+pub fn trigger(network: &IpNetwork) -> String {
+    let mut labels = vec![network.prefix().to_string()];
+    match network.ip() {
+        IpAddr::V4(address) => labels.extend(address.octets().iter().rev().map(u8::to_string)),
+        IpAddr::V6(address) => {
+            let segments = address.segments();
+
+            // the longest run of at least two zero words becomes `zz`
+            let mut zeros = (0, 0);
+            let mut start = 0;
+            for (i, segment) in segments.iter().enumerate() {
+                if *segment != 0 {
+                    start = i + 1;
+                } else if i + 1 - start > zeros.1 - zeros.0 {
+                    zeros = (start, i + 1);
+                }
+            }
+            if zeros.1 - zeros.0 < 2 {
+                zeros = (0, 0);
+            }
+
+            let mut words = Vec::new();
+            for (i, segment) in segments.iter().enumerate() {
+                if i == zeros.0 && zeros.1 > zeros.0 {
+                    words.push("zz".to_string());
+                } else if !(zeros.0..zeros.1).contains(&i) {
+                    words.push(format!("{segment:x}"));
+                }
+            }
+            labels.extend(words.into_iter().rev());
+        }
+    }
+    labels.push("rpz-ip".to_string());
+    labels.join(".")
+}
+
+/// Renders snapshots as an RPZ zone file with an `rpz-ip` trigger per
+/// listed network, so resolvers refuse names that resolve into the
+/// blacklist. The serial is the Unix time the blacklist was generated.
+#[derive(Debug, Clone)]
+pub struct Rpz {
+    action: Action,
+    ttl: u32,
+    mname: String,
+    rname: String,
+    nameserver: String,
+    aggregate: bool,
+}
+
+impl Default for Rpz {
+    fn default() -> Self {
+        Self {
+            action: Action::Nxdomain,
+            ttl: 300,
+            mname: "localhost.".to_string(),
+            rname: "hostmaster.localhost.".to_string(),
+            nameserver: "localhost.".to_string(),
+            aggregate: true,
+        }
+    }
+}
+
+impl Rpz {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Defaults to [`Action::Nxdomain`].
+    pub fn action(mut self, action: Action) -> Self {
+        self.action = action;
+        self
+    }
+
+    /// The TTL of all records and the negative caching TTL, defaults to
+    /// five minutes.
+    pub fn ttl(mut self, ttl: u32) -> Self {
+        self.ttl = ttl;
+        self
+    }
+
+    /// The primary name server and responsible mailbox of the SOA record,
+    /// as fully qualified names. Default to `localhost.` and
+    /// `hostmaster.localhost.`.
+    pub fn soa<TM: ToString, TR: ToString>(mut self, mname: TM, rname: TR) -> Self {
+        self.mname = mname.to_string();
+        self.rname = rname.to_string();
+        self
+    }
+
+    /// The NS record of the zone, defaults to `localhost.`.
+    pub fn nameserver<T: ToString>(mut self, nameserver: T) -> Self {
+        self.nameserver = nameserver.to_string();
+        self
+    }
+
+    /// Merge adjacent addresses into CIDR triggers, on by default.
+    pub fn aggregate(mut self, aggregate: bool) -> Self {
+        self.aggregate = aggregate;
+        self
+    }
+
+    pub fn render(&self, snapshot: &Snapshot) -> String {
+        let serial = snapshot.generated_at.unwrap_or_else(Utc::now).timestamp() as u32;
+        let networks = if self.aggregate {
+            aggregate(snapshot.addresses())
+        } else {
+            host_networks(snapshot.addresses())
+        };
+
+        let mut zone = format!("; {}\n$TTL {}\n", describe(snapshot), self.ttl);
+        writeln!(
+            zone,
+            "@ SOA {} {} {serial} 3600 600 604800 {}",
+            self.mname, self.rname, self.ttl
+        )
+        .unwrap();
+        writeln!(zone, "@ NS {}\n", self.nameserver).unwrap();
+        for network in &networks {
+            writeln!(zone, "{} CNAME {}", trigger(network), self.action.target()).unwrap();
+        }
+        zone
+    }
+}
@@ -0,0 +1,162 @@
+use std::{fmt::Write, net::IpAddr};
+
+use crate::{export::describe, snapshot::Snapshot};
+
+fn entries(snapshot: &Snapshot, min_score: u32) -> impl Iterator<Item = (IpAddr, u32)> + '_ {
+    snapshot
+        .entries
+        .iter()
+        .filter(move |(_, score)| **score >= min_score)
+        .map(|(address, score)| (*address, *score))
+}
+
+/// Renders snapshots as an nginx `geo` block mapping each address to its
+/// confidence score and everything else to 0. nginx cannot compare
+/// numbers, so derive policies with a `map`:
+///
+/// ```nginx
+/// include /etc/nginx/abuseipdb.conf;
+///
+/// map $abuseipdb_block $abuseipdb_deny {
+///     default          0;
+///     ~^(100|[89]\d)$  1;
+/// }
+///
+/// server {
+///     if ($abuseipdb_deny) { return 403; }
+/// }
+/// ```
+#[derive(Debug, Clone)]
+pub struct Nginx {
+    variable: String,
+    min_score: u32,
+    scores: bool,
+}
+
+impl Default for Nginx {
+    fn default() -> Self {
+        Self {
+            variable: "abuseipdb_block".to_string(),
+            min_score: 0,
+            scores: true,
+        }
+    }
+}
+
+impl Nginx {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// The variable set by the block, without `$`. Defaults to
+    /// `abuseipdb_block`.
+    pub fn variable<T: ToString>(mut self, variable: T) -> Self {
+        self.variable = variable.to_string();
+        self
+    }
+
+    /// Leave out addresses scored below `min_score`.
+    pub fn min_score(mut self, min_score: u32) -> Self {
+        self.min_score = min_score;
+        self
+    }
+
+    /// Map addresses to their score (the default) or to `1`.
+    pub fn scores(mut self, scores: bool) -> Self {
+        self.scores = scores;
+        self
+    }
+
+    pub fn render(&self, snapshot: &Snapshot) -> String {
+        let mut config = format!("# {}\n", describe(snapshot));
+        writeln!(config, "geo ${} {{\n\tdefault 0;", self.variable).unwrap();
+        for (address, score) in entries(snapshot, self.min_score) {
+            let value = if self.scores { score } else { 1 };
+            writeln!(config, "\t{address} {value};").unwrap();
+        }
+        config.push_str("}\n");
+        config
+    }
+}
+
+/// Renders snapshots as an Apache 2.4 authorization block denying the
+/// listed addresses, for a `<Directory>`, `<Location>` or virtual host:
+///
+/// ```apache
+/// <Location "/">
+///     Include /etc/apache2/abuseipdb.conf
+/// </Location>
+/// ```
+///
+/// Apache cannot branch on a value per address, so apply graduated
+/// policies with one export per threshold, e.g. one at 90 for the whole
+/// site and one at 50 for login pages.
+#[derive(Debug, Clone, Default)]
+pub struct Apache {
+    min_score: u32,
+}
+
+impl Apache {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Leave out addresses scored below `min_score`.
+    pub fn min_score(mut self, min_score: u32) -> Self {
+        self.min_score = min_score;
+        self
+    }
+
+    pub fn render(&self, snapshot: &Snapshot) -> String {
+        let mut config = format!("# {}\n", describe(snapshot));
+        config.push_str("<RequireAll>\n\tRequire all granted\n");
+        for (address, score) in entries(snapshot, self.min_score) {
+            writeln!(config, "\t# score {score}\n\tRequire not ip {address}").unwrap();
+        }
+        config.push_str("</RequireAll>\n");
+        config
+    }
+}
+
+/// Renders snapshots as HAProxy ACL or map files.
+///
+/// ```haproxy
+/// http-request set-var(txn.abuse_score) src,map_ip(/etc/haproxy/abuseipdb.map,0)
+/// http-request deny if { var(txn.abuse_score) -m int ge 90 }
+/// http-request tarpit if { var(txn.abuse_score) -m int ge 50 } { path_beg /login }
+/// ```
+#[derive(Debug, Clone, Default)]
+pub struct Haproxy {
+    min_score: u32,
+}
+
+impl Haproxy {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Leave out addresses scored below `min_score`.
+    pub fn min_score(mut self, min_score: u32) -> Self {
+        self.min_score = min_score;
+        self
+    }
+
+    /// An ACL file with one address per line, for
+    /// `acl abuseipdb src -f /etc/haproxy/abuseipdb.acl`.
+    pub fn render_acl(&self, snapshot: &Snapshot) -> String {
+        let mut acl = format!("# {}\n", describe(snapshot));
+        for (address, _) in entries(snapshot, self.min_score) {
+            writeln!(acl, "{address}").unwrap();
+        }
+        acl
+    }
+
+    /// A map file from address to score, for the `map_ip` converter.
+    pub fn render_map(&self, snapshot: &Snapshot) -> String {
+        let mut map = format!("# {}\n", describe(snapshot));
+        for (address, score) in entries(snapshot, self.min_score) {
+            writeln!(map, "{address} {score}").unwrap();
+        }
+        map
+    }
+}
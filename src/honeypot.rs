@@ -0,0 +1,278 @@
+use std::{
+    net::{IpAddr, Ipv4Addr, SocketAddr},
+    sync::Arc,
+    time::Duration,
+};
+
+use chrono::Utc;
+use tokio::{
+    io::{AsyncReadExt, AsyncWriteExt},
+    net::{TcpListener, TcpStream},
+    sync::mpsc,
+    time::Instant,
+};
+
+use crate::{
+    endpoints::report::Request, event::Event, sanitize::Sanitizer, types::Category, Client,
+};
+
+/// What a decoy port pretends to be.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Service {
+    Ssh,
+    Telnet,
+    Http,
+    Smtp,
+    /// Accepts and listens without saying anything.
+    Silent,
+}
+
+impl Service {
+    pub fn name(&self) -> &'static str {
+        match self {
+            Service::Ssh => "ssh",
+            Service::Telnet => "telnet",
+            Service::Http => "http",
+            Service::Smtp => "smtp",
+            Service::Silent => "tcp",
+        }
+    }
+
+    /// Sent as soon as a connection is accepted.
+    fn banner(&self) -> &'static [u8] {
+        match self {
+            Service::Ssh => b"SSH-2.0-OpenSSH_8.9p1 Ubuntu-3ubuntu0.6\r\n",
+            // IAC WILL ECHO, IAC WILL SUPPRESS-GO-AHEAD, IAC DO NAWS
+            Service::Telnet => b"\xff\xfb\x01\xff\xfb\x03\xff\xfd\x1f\r\nlogin: ",
+            Service::Smtp => b"220 mail.localdomain ESMTP Postfix (Ubuntu)\r\n",
+            Service::Http | Service::Silent => b"",
+        }
+    }
+
+    /// Sent once the client sent something.
+    fn reply(&self) -> &'static [u8] {
+        match self {
+            Service::Http => {
+                b"HTTP/1.1 200 OK\r\nServer: nginx/1.18.0 (Ubuntu)\r\nContent-Type: text/html\r\n\
+                  Content-Length: 44\r\nConnection: close\r\n\r\n<html><body><h1>It works!</h1></body></html>"
+            }
+            Service::Telnet => b"\r\nPassword: ",
+            Service::Smtp => b"250 mail.localdomain\r\n",
+            Service::Ssh | Service::Silent => b"",
+        }
+    }
+}
+
+/// The first bytes a client sent to a decoy port.
+#[derive(Debug, Clone)]
+pub struct Contact {
+    pub address: IpAddr,
+    pub port: u16,
+    pub service: Service,
+    pub payload: Vec<u8>,
+}
+
+impl Contact {
+    /// Any contact is hostile: a bare connection is a port scan, one that
+    /// sends data is an attack. Telnet ports are IoT botnet targets.
+    pub fn categories(&self) -> Vec<Category> {
+        let mut categories = vec![if self.payload.is_empty() {
+            Category::PortScan
+        } else {
+            Category::Hacking
+        }];
+        if self.service == Service::Telnet || matches!(self.port, 23 | 2323) {
+            categories.push(Category::IotTargeted);
+        }
+        categories
+    }
+}
+
+/// Escapes a payload into printable ASCII, e.g. `GET / HTTP/1.1\r\n`.
+fn escape_payload(payload: &[u8]) -> String {
+    payload
+        .iter()
+        .flat_map(|b| std::ascii::escape_default(*b))
+        .map(char::from)
+        .collect()
+}
+
+/// TCP listeners on unused ports that answer with a plausible banner,
+/// record what the client sends and emit an event for every connection.
+#[derive(Debug, Clone)]
+pub struct Honeypot {
+    address: IpAddr,
+    ports: Vec<(u16, Service)>,
+    capture_timeout: Duration,
+    max_capture: usize,
+    sanitizer: Sanitizer,
+}
+
+impl Default for Honeypot {
+    fn default() -> Self {
+        Self {
+            address: IpAddr::V4(Ipv4Addr::UNSPECIFIED),
+            ports: Vec::new(),
+            capture_timeout: Duration::from_secs(5),
+            max_capture: 256,
+            sanitizer: Sanitizer::default(),
+        }
+    }
+}
+
+impl Honeypot {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// The address to listen on, defaults to `0.0.0.0`.
+    pub fn address(mut self, address: IpAddr) -> Self {
+        self.address = address;
+        self
+    }
+
+    /// Adds a decoy port. Port 0 picks a free port, see [`Honeypot::start`].
+    pub fn listen(mut self, port: u16, service: Service) -> Self {
+        self.ports.push((port, service));
+        self
+    }
+
+    /// How long to wait for the client to send something, defaults to five
+    /// seconds.
+    pub fn capture_timeout(mut self, timeout: Duration) -> Self {
+        self.capture_timeout = timeout;
+        self
+    }
+
+    /// How many bytes of the payload are kept, defaults to 256.
+    pub fn max_capture(mut self, max_capture: usize) -> Self {
+        self.max_capture = max_capture;
+        self
+    }
+
+    /// The sanitizer used to scrub payloads, defaults to
+    /// [`Sanitizer::default`]. Add the decoy's own addresses and names.
+    pub fn sanitizer(mut self, sanitizer: Sanitizer) -> Self {
+        self.sanitizer = sanitizer;
+        self
+    }
+
+    /// Talks to a client until it stops sending, hangs up or the capture is
+    /// full.
+    async fn capture(&self, stream: &mut TcpStream, service: Service) -> Vec<u8> {
+        let mut payload = Vec::new();
+        let mut buffer = [0; 1024];
+        let deadline = Instant::now() + self.capture_timeout;
+
+        if stream.write_all(service.banner()).await.is_err() {
+            return payload;
+        }
+        while payload.len() < self.max_capture {
+            let read = match tokio::time::timeout_at(deadline, stream.read(&mut buffer)).await {
+                Ok(Ok(read)) if read > 0 => read,
+                _ => break,
+            };
+            let first = payload.is_empty();
+            let keep = read.min(self.max_capture - payload.len());
+            payload.extend_from_slice(&buffer[..keep]);
+            if first && stream.write_all(service.reply()).await.is_err() {
+                break;
+            }
+            if service == Service::Http {
+                break;
+            }
+        }
+        payload
+    }
+
+    /// The event for a contact, with the escaped payload as evidence.
+    pub fn event(&self, contact: &Contact) -> Event {
+        let mut evidence = format!(
+            "Honeypot: connection to {}/{}",
+            contact.service.name(),
+            contact.port
+        );
+        if contact.payload.is_empty() {
+            evidence.push_str(", nothing sent");
+        } else {
+            let payload = self.sanitizer.sanitize(&escape_payload(&contact.payload));
+            evidence.push_str(&format!(", sent \"{payload}\""));
+        }
+        Event::new(
+            contact.address,
+            &contact.categories(),
+            "honeypot connection",
+            evidence,
+            Utc::now(),
+        )
+        .with_target("port", contact.port)
+    }
+
+    /// Accepts connections until the listener fails, serving each in its
+    /// own task.
+    pub async fn serve(
+        self: Arc<Self>,
+        listener: TcpListener,
+        service: Service,
+        events: mpsc::UnboundedSender<Event>,
+    ) -> crate::Result<()> {
+        let port = listener.local_addr()?.port();
+        loop {
+            let (mut stream, peer) = listener.accept().await?;
+            let honeypot = self.clone();
+            let events = events.clone();
+            tokio::spawn(async move {
+                let payload = honeypot.capture(&mut stream, service).await;
+                let contact = Contact {
+                    address: peer.ip().to_canonical(),
+                    port,
+                    service,
+                    payload,
+                };
+                let _ = events.send(honeypot.event(&contact));
+            });
+        }
+    }
+
+    /// Binds all ports and serves them in the background. Returns the bound
+    /// addresses in the order the ports were added.
+    pub async fn start(
+        self,
+        events: mpsc::UnboundedSender<Event>,
+    ) -> crate::Result<Vec<SocketAddr>> {
+        let honeypot = Arc::new(self);
+        let mut listeners = Vec::new();
+        for (port, service) in &honeypot.ports {
+            let listener = TcpListener::bind((honeypot.address, *port)).await?;
+            listeners.push((listener, *service));
+        }
+
+        let mut addresses = Vec::new();
+        for (listener, service) in listeners {
+            addresses.push(listener.local_addr()?);
+            tokio::spawn(honeypot.clone().serve(listener, service, events.clone()));
+        }
+        Ok(addresses)
+    }
+
+    /// Serves all ports and submits every contact through
+    /// [`Client::send_report`]. Failed submissions are passed to
+    /// `on_error`. Every connection is reported; use [`Honeypot::start`]
+    /// with a [`crate::dedup::Deduplicator`] to report a persistent scanner
+    /// once per window.
+    pub async fn run<F>(self, client: &Client, mut on_error: F) -> crate::Result<()>
+    where
+        F: FnMut(Request, crate::Error),
+    {
+        let (sender, mut events) = mpsc::unbounded_channel();
+        self.start(sender).await?;
+
+        while let Some(event) = events.recv().await {
+            let request = event.to_request();
+            if let Err(e) = client.send_report(&request).await {
+                on_error(request, e);
+            }
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,162 @@
+use std::{collections::HashMap, net::IpAddr};
+
+use chrono::{DateTime, Duration, FixedOffset, Utc};
+
+use crate::{
+    event::Event,
+    ingest::LogLine,
+    sanitize::{truncate, MAX_COMMENT_LENGTH},
+    types::Category,
+};
+
+/// A packet logged by an iptables `LOG` or nftables `log` rule.
+#[derive(Debug, Clone)]
+pub struct Packet {
+    pub timestamp: DateTime<Utc>,
+    pub source: IpAddr,
+    pub destination: Option<IpAddr>,
+    /// `TCP`, `UDP`, `ICMP`, ...
+    pub protocol: String,
+    pub destination_port: Option<u16>,
+    pub interface: Option<String>,
+}
+
+/// Parses the `IN=... SRC=... DST=... PROTO=... DPT=...` part of a kernel
+/// log message. Anything before `IN=` (a log prefix) is ignored.
+pub fn parse_packet(message: &str, timestamp: DateTime<Utc>) -> Option<Packet> {
+    let start = message.find("IN=")?;
+    let mut source = None;
+    let mut destination = None;
+    let mut protocol = None;
+    let mut destination_port = None;
+    let mut interface = None;
+
+    for field in message[start..].split_whitespace() {
+        let Some((key, value)) = field.split_once('=') else {
+            continue;
+        };
+        match key {
+            "IN" if !value.is_empty() => interface = Some(value.to_string()),
+            "SRC" => source = value.parse().ok(),
+            "DST" => destination = value.parse().ok(),
+            "PROTO" => protocol = Some(value.to_ascii_uppercase()),
+            "DPT" => destination_port = value.parse().ok(),
+            _ => {}
+        }
+    }
+
+    Some(Packet {
+        timestamp,
+        source: source?,
+        destination,
+        protocol: protocol.unwrap_or_default(),
+        destination_port,
+        interface,
+    })
+}
+
+/// Counts distinct destination ports per source over a sliding window and
+/// emits a [`Category::PortScan`] event when a source crosses the
+/// threshold.
+#[derive(Debug, Clone)]
+pub struct Detector {
+    window: Duration,
+    threshold: usize,
+    offset: FixedOffset,
+    /// Last time each source probed a protocol and port.
+    probes: HashMap<IpAddr, HashMap<(String, u16), DateTime<Utc>>>,
+}
+
+impl Detector {
+    /// Emits an event once a source probed `threshold` distinct ports
+    /// within `window`.
+    pub fn new(window: Duration, threshold: usize) -> Self {
+        Self {
+            window,
+            threshold: threshold.max(1),
+            offset: FixedOffset::east_opt(0).unwrap(),
+            probes: HashMap::new(),
+        }
+    }
+
+    /// The zone of BSD syslog timestamps, which carry none. Defaults to UTC.
+    pub fn offset(mut self, offset: FixedOffset) -> Self {
+        self.offset = offset;
+        self
+    }
+
+    /// Adds a packet. Packets without a destination port are ignored.
+    pub fn push(&mut self, packet: Packet) -> Option<Event> {
+        let port = packet.destination_port?;
+        let window = self.window;
+        let now = packet.timestamp;
+
+        let probes = self.probes.entry(packet.source).or_default();
+        probes.insert((packet.protocol, port), now);
+        probes.retain(|_, timestamp| *timestamp + window > now);
+
+        if probes.len() < self.threshold {
+            return None;
+        }
+
+        let probes = self.probes.remove(&packet.source)?;
+        let first_seen = probes.values().min().copied()?;
+        let mut ports: Vec<(&str, u16)> = probes
+            .keys()
+            .map(|(protocol, port)| (protocol.as_str(), *port))
+            .collect();
+        ports.sort_unstable();
+
+        let mut by_protocol: Vec<(&str, Vec<String>)> = Vec::new();
+        for (protocol, port) in &ports {
+            match by_protocol.iter_mut().find(|(p, _)| p == protocol) {
+                Some((_, list)) => list.push(port.to_string()),
+                None => by_protocol.push((protocol, vec![port.to_string()])),
+            }
+        }
+        let listed = by_protocol
+            .iter()
+            .map(|(protocol, list)| format!("{protocol} {}", list.join(",")))
+            .collect::<Vec<_>>()
+            .join("; ");
+        let span = (now - first_seen).num_seconds();
+        let evidence = format!("Port scan: {} ports in {span} s ({listed})", ports.len());
+
+        Some(Event::new(
+            packet.source,
+            &[Category::PortScan],
+            "port scan",
+            truncate(&evidence, MAX_COMMENT_LENGTH),
+            first_seen,
+        ))
+    }
+
+    /// Drops probes that fell out of the window, e.g. from a timer.
+    pub fn expire(&mut self, now: DateTime<Utc>) {
+        let window = self.window;
+        self.probes.retain(|_, probes| {
+            probes.retain(|_, timestamp| *timestamp + window > now);
+            !probes.is_empty()
+        });
+    }
+
+    /// Adds a split kernel log line, ignoring other programs.
+    pub fn push_log_line(&mut self, line: &LogLine) -> Option<Event> {
+        if line.program != "kernel" {
+            return None;
+        }
+        self.push(parse_packet(&line.message, line.timestamp)?)
+    }
+
+    /// Adds a line of a syslog file such as `/var/log/kern.log`.
+    pub fn push_line(&mut self, line: &str) -> Option<Event> {
+        let line = super::parse_syslog_line(line, self.offset, Utc::now())?;
+        self.push_log_line(&line)
+    }
+
+    /// Adds a line of `journalctl -k -o json` output.
+    pub fn push_journal(&mut self, line: &str) -> Option<Event> {
+        let line = super::parse_journal_line(line)?;
+        self.push_log_line(&line)
+    }
+}
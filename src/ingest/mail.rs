@@ -0,0 +1,151 @@
+use std::{net::IpAddr, sync::OnceLock};
+
+use chrono::{DateTime, FixedOffset, Utc};
+use regex::Regex;
+
+use crate::{event::Event, ingest::LogLine, sanitize::Sanitizer, types::Category};
+
+struct Rule {
+    regex: Regex,
+    kind: &'static str,
+    categories: &'static [Category],
+}
+
+fn rules() -> &'static [Rule] {
+    static RULES: OnceLock<Vec<Rule>> = OnceLock::new();
+    const CLIENT: &str = r"[^\[\s]+\[(?P<ip>[0-9A-Fa-f:.]+)\]";
+    RULES.get_or_init(|| {
+        [
+            // postfix
+            (
+                r"warning: {CLIENT}(?::\d+)?: SASL \S+ authentication failed",
+                "failed SMTP login",
+                &[Category::BruteForceCredential][..],
+            ),
+            // only permanent (5xx) rejects, greylisting and other temporary
+            // rejects hit legitimate servers too
+            (
+                r"NOQUEUE: reject: \S+ from {CLIENT}(?::\d+)?: 5\d\d .*Relay access denied",
+                "relay attempt",
+                &[Category::EmailSpam][..],
+            ),
+            (
+                r"NOQUEUE: reject: \S+ from {CLIENT}(?::\d+)?: 5\d\d .*(?:Sender address rejected: not (?:owned by user|logged in)|SPF|spf)",
+                "spoofed sender",
+                &[Category::Spoofing, Category::EmailSpam][..],
+            ),
+            (
+                r"NOQUEUE: reject: \S+ from {CLIENT}(?::\d+)?: 5\d\d .*(?:blocked using|Client host rejected)",
+                "blocklisted client",
+                &[Category::EmailSpam][..],
+            ),
+            // dovecot
+            (
+                r"(?:auth failed|Aborted login \(auth failed)[^:]*\): user=<(?P<user>[^>]*)>.*?\brip=(?P<ip>[0-9A-Fa-f:.]+)",
+                "failed IMAP/POP3 login",
+                &[Category::BruteForceCredential][..],
+            ),
+            (
+                r"auth(?:-worker)?(?:\(\d+\))?: (?:Info: )?[\w-]+\((?P<user>[^,]*),(?P<ip>[0-9A-Fa-f:.]+)(?:,<[^>]*>)?\): (?:unknown user|[Pp]assword mismatch|pam_authenticate\(\) failed)",
+                "failed IMAP/POP3 login",
+                &[Category::BruteForceCredential][..],
+            ),
+        ]
+        .into_iter()
+        .map(|(pattern, kind, categories)| Rule {
+            regex: Regex::new(&pattern.replace("{CLIENT}", CLIENT)).unwrap(),
+            kind,
+            categories,
+        })
+        .collect()
+    })
+}
+
+/// Turns Postfix and Dovecot log messages into events.
+///
+/// SASL and IMAP/POP3 authentication failures map to
+/// [`Category::BruteForceCredential`], relay attempts and clients rejected
+/// by a DNSBL or access rule to [`Category::EmailSpam`] and forged senders
+/// to [`Category::Spoofing`]. Other rejects, e.g. unknown recipients or
+/// greylisting, are not reported.
+#[derive(Debug, Clone)]
+pub struct Parser {
+    sanitizer: Sanitizer,
+    offset: FixedOffset,
+}
+
+impl Default for Parser {
+    fn default() -> Self {
+        Self {
+            sanitizer: Sanitizer::default(),
+            offset: FixedOffset::east_opt(0).unwrap(),
+        }
+    }
+}
+
+impl Parser {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// The sanitizer used to scrub evidence, defaults to [`Sanitizer::default`].
+    pub fn sanitizer(mut self, sanitizer: Sanitizer) -> Self {
+        self.sanitizer = sanitizer;
+        self
+    }
+
+    /// The zone of BSD syslog timestamps, which carry none. Defaults to UTC.
+    pub fn offset(mut self, offset: FixedOffset) -> Self {
+        self.offset = offset;
+        self
+    }
+
+    /// Parses a Postfix or Dovecot message without syslog header.
+    pub fn parse_message(&self, message: &str, timestamp: DateTime<Utc>) -> Option<Event> {
+        let message = message.trim();
+        for rule in rules() {
+            let Some(caps) = rule.regex.captures(message) else {
+                continue;
+            };
+            let address: IpAddr = caps["ip"].parse().ok()?;
+            let user = caps.name("user").filter(|u| !u.is_empty());
+            let evidence = match user {
+                Some(user) if self.sanitizer.masks_usernames() => self.sanitizer.sanitize(
+                    &[&message[..user.start()], "[user]", &message[user.end()..]].concat(),
+                ),
+                _ => self.sanitizer.sanitize(message),
+            };
+
+            let mut event = Event::new(address, rule.categories, rule.kind, evidence, timestamp);
+            if let Some(user) = user {
+                event = event.with_target("user", user.as_str());
+            }
+            return Some(event);
+        }
+        None
+    }
+
+    /// Parses a split log line, ignoring lines of other programs.
+    pub fn parse_log_line(&self, line: &LogLine) -> Option<Event> {
+        let program = line.program.as_str();
+        if !(program.starts_with("postfix")
+            || program.starts_with("dovecot")
+            || program.ends_with("-login"))
+        {
+            return None;
+        }
+        self.parse_message(&line.message, line.timestamp)
+    }
+
+    /// Parses a line of a syslog file such as `/var/log/mail.log`.
+    pub fn parse_line(&self, line: &str) -> Option<Event> {
+        let line = super::parse_syslog_line(line, self.offset, Utc::now())?;
+        self.parse_log_line(&line)
+    }
+
+    /// Parses a line of `journalctl -o json` output.
+    pub fn parse_journal(&self, line: &str) -> Option<Event> {
+        let line = super::parse_journal_line(line)?;
+        self.parse_log_line(&line)
+    }
+}
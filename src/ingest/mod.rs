@@ -0,0 +1,115 @@
+use std::sync::OnceLock;
+
+use chrono::{DateTime, Datelike, Duration, FixedOffset, NaiveDateTime, TimeZone, Utc};
+use regex::Regex;
+use serde::Deserialize;
+
+pub mod firewall;
+pub mod mail;
+pub mod sshd;
+pub mod suricata;
+pub mod syslog;
+pub mod tail;
+pub mod web;
+
+fn syslog_regex() -> &'static Regex {
+    static REGEX: OnceLock<Regex> = OnceLock::new();
+    REGEX.get_or_init(|| {
+        Regex::new(
+            r"^(?:(?P<bsd>[A-Z][a-z]{2} [ \d]\d \d{2}:\d{2}:\d{2})|(?P<iso>\d{4}-\d{2}-\d{2}T\S+))\s+(?P<host>\S+)\s+(?P<program>[^\s\[:]+)(?:\[(?P<pid>\d+)\])?:\s?(?P<message>.*)$",
+        )
+        .unwrap()
+    })
+}
+
+/// A log line split into its syslog header and message.
+#[derive(Debug, Clone)]
+pub struct LogLine {
+    pub timestamp: DateTime<Utc>,
+    pub host: String,
+    pub program: String,
+    pub pid: Option<u32>,
+    pub message: String,
+}
+
+/// Parses a BSD syslog timestamp (`Oct  8 12:34:56`), which has no year or
+/// zone. The year is the one closest to `now` that is not in the future.
+pub(crate) fn parse_bsd_timestamp(
+    timestamp: &str,
+    offset: FixedOffset,
+    now: DateTime<Utc>,
+) -> Option<DateTime<Utc>> {
+    let timestamp = timestamp.split_whitespace().collect::<Vec<_>>().join(" ");
+    let year = now.with_timezone(&offset).year();
+    for year in [year, year - 1] {
+        // "Feb 29" only exists in leap years
+        let Ok(naive) =
+            NaiveDateTime::parse_from_str(&format!("{year} {timestamp}"), "%Y %b %d %H:%M:%S")
+        else {
+            continue;
+        };
+        let parsed = offset.from_local_datetime(&naive).single()?.to_utc();
+        // allow for some clock skew between the logging host and us
+        if parsed <= now + Duration::days(1) {
+            return Some(parsed);
+        }
+    }
+    None
+}
+
+/// Parses a traditional (`Oct 18 12:34:56 host sshd[42]: ...`) or high
+/// precision (`2024-10-18T12:34:56.123+02:00 host sshd[42]: ...`) syslog
+/// file line. BSD timestamps are interpreted in `offset`.
+pub fn parse_syslog_line(line: &str, offset: FixedOffset, now: DateTime<Utc>) -> Option<LogLine> {
+    let caps = syslog_regex().captures(line.trim_end())?;
+
+    let timestamp = if let Some(bsd) = caps.name("bsd") {
+        parse_bsd_timestamp(bsd.as_str(), offset, now)?
+    } else {
+        DateTime::parse_from_rfc3339(caps.name("iso")?.as_str())
+            .ok()?
+            .to_utc()
+    };
+
+    Some(LogLine {
+        timestamp,
+        host: caps["host"].to_string(),
+        program: caps["program"].to_string(),
+        pid: caps.name("pid").and_then(|p| p.as_str().parse().ok()),
+        message: caps["message"].to_string(),
+    })
+}
+
+#[derive(Debug, Deserialize)]
+struct JournalRecord {
+    #[serde(rename = "__REALTIME_TIMESTAMP")]
+    realtime_timestamp: Option<String>,
+    #[serde(rename = "_HOSTNAME")]
+    hostname: Option<String>,
+    #[serde(rename = "SYSLOG_IDENTIFIER")]
+    identifier: Option<String>,
+    #[serde(rename = "_COMM")]
+    comm: Option<String>,
+    #[serde(rename = "_PID")]
+    pid: Option<String>,
+    // binary messages are exported as byte arrays, which we skip
+    #[serde(rename = "MESSAGE")]
+    message: Option<serde_json::Value>,
+}
+
+/// Parses one line of `journalctl -o json` output.
+pub fn parse_journal_line(line: &str) -> Option<LogLine> {
+    let record: JournalRecord = serde_json::from_str(line).ok()?;
+
+    let micros: i64 = record.realtime_timestamp?.parse().ok()?;
+    let timestamp = DateTime::from_timestamp_micros(micros)?;
+    let message = record.message?.as_str()?.to_string();
+
+    Some(LogLine {
+        timestamp,
+        host: record.hostname.unwrap_or_default(),
+        program: record.identifier.or(record.comm).unwrap_or_default(),
+        pid: record.pid.and_then(|p| p.parse().ok()),
+        message,
+    })
+}
@@ -0,0 +1,145 @@
+use std::{net::IpAddr, sync::OnceLock};
+
+use chrono::{DateTime, FixedOffset, Utc};
+use regex::Regex;
+
+use crate::{event::Event, ingest::LogLine, sanitize::Sanitizer, types::Category};
+
+/// Categories attached to every sshd event.
+pub const CATEGORIES: &[Category] = &[Category::BruteForceCredential, Category::SshAbuse];
+
+struct Rule {
+    regex: Regex,
+    kind: &'static str,
+}
+
+fn rules() -> &'static [Rule] {
+    static RULES: OnceLock<Vec<Rule>> = OnceLock::new();
+    const IP: &str = r"(?P<ip>[0-9A-Fa-f:.]+)";
+    RULES.get_or_init(|| {
+        [
+            (
+                r"^Failed \S+ for (?:invalid user )?(?P<user>.*?) from {IP} port \d+",
+                "failed SSH login",
+            ),
+            (
+                r"^[Ii]nvalid user (?P<user>.*?) from {IP}(?: port \d+)?",
+                "invalid SSH user",
+            ),
+            (
+                r"^(?:Connection closed by|Disconnected from) (?:authenticating|invalid) user (?P<user>.*?) {IP} port \d+ \[preauth\]",
+                "pre-auth SSH connection close",
+            ),
+            (
+                r"^error: maximum authentication attempts exceeded for (?:invalid user )?(?P<user>.*?) from {IP} port \d+",
+                "SSH login flood",
+            ),
+            (
+                r"^Received disconnect from {IP} port \d+:\d+: .*\[preauth\]",
+                "pre-auth SSH disconnect",
+            ),
+            (
+                r"^(?:Connection closed by|Disconnected from) {IP} port \d+ \[preauth\]",
+                "pre-auth SSH disconnect",
+            ),
+            (
+                r"^Unable to negotiate with {IP} port \d+: no matching",
+                "SSH key exchange failure",
+            ),
+            (
+                r"^(?:error: )?(?:kex_exchange_identification|banner exchange): .*Connection from {IP} port \d+",
+                "SSH key exchange failure",
+            ),
+            (
+                r"^ssh_dispatch_run_fatal: Connection from (?:(?:authenticating|invalid) user (?P<user>\S+) )?{IP} port \d+",
+                "SSH key exchange failure",
+            ),
+            (
+                r"^(?:Did not receive identification string|Bad protocol version identification .*) from {IP}",
+                "SSH key exchange failure",
+            ),
+        ]
+        .into_iter()
+        .map(|(pattern, kind)| Rule {
+            regex: Regex::new(&pattern.replace("{IP}", IP)).unwrap(),
+            kind,
+        })
+        .collect()
+    })
+}
+
+/// Turns OpenSSH log messages into events tagged [`CATEGORIES`].
+///
+/// Recognizes failed and invalid logins, connections closed or
+/// disconnected before authentication and key exchange failures.
+#[derive(Debug, Clone)]
+pub struct Parser {
+    sanitizer: Sanitizer,
+    offset: FixedOffset,
+}
+
+impl Default for Parser {
+    fn default() -> Self {
+        Self {
+            sanitizer: Sanitizer::default(),
+            offset: FixedOffset::east_opt(0).unwrap(),
+        }
+    }
+}
+
+impl Parser {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// The sanitizer used to scrub evidence, defaults to [`Sanitizer::default`].
+    pub fn sanitizer(mut self, sanitizer: Sanitizer) -> Self {
+        self.sanitizer = sanitizer;
+        self
+    }
+
+    /// The zone of BSD syslog timestamps, which carry none. Defaults to UTC.
+    pub fn offset(mut self, offset: FixedOffset) -> Self {
+        self.offset = offset;
+        self
+    }
+
+    /// Parses an sshd message without syslog header.
+    pub fn parse_message(&self, message: &str, timestamp: DateTime<Utc>) -> Option<Event> {
+        let message = message.trim();
+        for rule in rules() {
+            let Some(caps) = rule.regex.captures(message) else {
+                continue;
+            };
+            let address: IpAddr = caps["ip"].parse().ok()?;
+            let evidence = self.sanitizer.sanitize(&format!("sshd: {message}"));
+
+            let mut event = Event::new(address, CATEGORIES, rule.kind, evidence, timestamp);
+            if let Some(user) = caps.name("user") {
+                event = event.with_target("user", user.as_str());
+            }
+            return Some(event);
+        }
+        None
+    }
+
+    /// Parses a split log line, ignoring lines of other programs.
+    pub fn parse_log_line(&self, line: &LogLine) -> Option<Event> {
+        if !line.program.starts_with("sshd") {
+            return None;
+        }
+        self.parse_message(&line.message, line.timestamp)
+    }
+
+    /// Parses a line of a syslog file such as `/var/log/auth.log`.
+    pub fn parse_line(&self, line: &str) -> Option<Event> {
+        let line = super::parse_syslog_line(line, self.offset, Utc::now())?;
+        self.parse_log_line(&line)
+    }
+
+    /// Parses a line of `journalctl -o json` output.
+    pub fn parse_journal(&self, line: &str) -> Option<Event> {
+        let line = super::parse_journal_line(line)?;
+        self.parse_log_line(&line)
+    }
+}
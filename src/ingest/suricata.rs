@@ -0,0 +1,314 @@
+use std::{collections::HashMap, io::SeekFrom, net::IpAddr, path::Path, time::Duration};
+
+use chrono::{DateTime, Utc};
+use ipnetwork::IpNetwork;
+use regex::Regex;
+use serde::Deserialize;
+use tokio::io::{AsyncBufReadExt, AsyncSeekExt, BufReader};
+
+use crate::{event::Event, ingest::tail::inode, sanitize::is_private, types::Category};
+
+#[derive(Debug, Deserialize)]
+struct EveRecord {
+    timestamp: String,
+    event_type: String,
+    src_ip: IpAddr,
+    dest_ip: IpAddr,
+    dest_port: Option<u16>,
+    proto: Option<String>,
+    alert: Option<EveAlert>,
+}
+
+#[derive(Debug, Deserialize)]
+struct EveAlert {
+    #[serde(default)]
+    gid: u32,
+    signature_id: u32,
+    #[serde(default)]
+    rev: u32,
+    signature: String,
+    /// The classtype description from `classification.config`.
+    #[serde(default)]
+    category: String,
+}
+
+/// Maps alerts to categories by classtype and signature name.
+#[derive(Debug, Clone, Default)]
+pub struct Mapping {
+    classtypes: HashMap<String, Vec<Category>>,
+    signatures: Vec<(Regex, Vec<Category>)>,
+}
+
+impl Mapping {
+    /// An empty mapping; alerts that match nothing are ignored.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Maps a classtype. EVE records carry the classtype description, so
+    /// register both the short name and the description, e.g.
+    /// `"attempted-recon"` and `"Attempted Information Leak"`.
+    pub fn classtype<T: ToString>(mut self, classtype: T, categories: &[Category]) -> Self {
+        self.classtypes
+            .insert(classtype.to_string(), categories.to_vec());
+        self
+    }
+
+    /// Adds categories for alerts whose signature matches the pattern. Only
+    /// applies to alerts of a mapped classtype, so informational alerts
+    /// (e.g. `ET POLICY` or `ET INFO`) mentioning SSH are not reported.
+    pub fn signature(
+        mut self,
+        pattern: &str,
+        categories: &[Category],
+    ) -> Result<Self, regex::Error> {
+        self.signatures
+            .push((Regex::new(pattern)?, categories.to_vec()));
+        Ok(self)
+    }
+
+    fn categories(&self, classtype: &str, signature: &str) -> Vec<Category> {
+        let Some(mut categories) = self.classtypes.get(classtype).cloned() else {
+            return Vec::new();
+        };
+        for (pattern, extra) in &self.signatures {
+            if pattern.is_match(signature) {
+                for category in extra {
+                    if !categories.contains(category) {
+                        categories.push(*category);
+                    }
+                }
+            }
+        }
+        categories
+    }
+}
+
+/// The built-in mapping of the stock Suricata classtypes (by name and
+/// description), refined by a few signature patterns of the ET Open rule
+/// set.
+pub fn default_mapping() -> Mapping {
+    let classtypes: &[(&str, &str, &[Category])] = &[
+        (
+            "attempted-recon",
+            "Attempted Information Leak",
+            &[Category::PortScan],
+        ),
+        (
+            "network-scan",
+            "Detection of a Network Scan",
+            &[Category::PortScan],
+        ),
+        (
+            "web-application-attack",
+            "Web Application Attack",
+            &[Category::WebAppAttack],
+        ),
+        (
+            "web-application-activity",
+            "Access to a potentially vulnerable web application",
+            &[Category::WebAppAttack],
+        ),
+        (
+            "trojan-activity",
+            "A Network Trojan was detected",
+            &[Category::ExploitedHost],
+        ),
+        (
+            "command-and-control",
+            "Malware Command and Control Activity Detected",
+            &[Category::ExploitedHost],
+        ),
+        (
+            "exploit-kit",
+            "Exploit Kit Activity Detected",
+            &[Category::ExploitedHost],
+        ),
+        (
+            "coin-mining",
+            "Crypto Currency Mining Activity Detected",
+            &[Category::ExploitedHost],
+        ),
+        (
+            "attempted-admin",
+            "Attempted Administrator Privilege Gain",
+            &[Category::Hacking],
+        ),
+        (
+            "attempted-user",
+            "Attempted User Privilege Gain",
+            &[Category::Hacking],
+        ),
+        ("misc-attack", "Misc Attack", &[Category::Hacking]),
+        (
+            "attempted-dos",
+            "Attempted Denial of Service",
+            &[Category::DdosAttack],
+        ),
+        (
+            "denial-of-service",
+            "Detection of a Denial of Service Attack",
+            &[Category::DdosAttack],
+        ),
+        (
+            "suspicious-login",
+            "An attempted login using a suspicious username was detected",
+            &[Category::BruteForceCredential],
+        ),
+        (
+            "default-login-attempt",
+            "Attempt to login by a default username and password",
+            &[Category::BruteForceCredential],
+        ),
+    ];
+
+    let mut mapping = Mapping::new();
+    for (name, description, categories) in classtypes {
+        mapping = mapping
+            .classtype(name, categories)
+            .classtype(description, categories);
+    }
+
+    let signatures: &[(&str, &[Category])] = &[
+        (r"^ET SCAN ", &[Category::PortScan]),
+        (r"(?i)\bSSH\b", &[Category::SshAbuse]),
+        (r"(?i)\bSQL injection\b|\bSQLi\b", &[Category::SqlInjection]),
+        (r"(?i)brute.?force", &[Category::BruteForceCredential]),
+    ];
+    for (pattern, categories) in signatures {
+        mapping = mapping.signature(pattern, categories).unwrap();
+    }
+    mapping
+}
+
+/// Turns Suricata EVE JSON alerts into events for the external endpoint of
+/// the flow.
+#[derive(Debug, Clone)]
+pub struct Parser {
+    mapping: Mapping,
+    home_networks: Vec<IpNetwork>,
+}
+
+impl Default for Parser {
+    fn default() -> Self {
+        Self {
+            mapping: default_mapping(),
+            home_networks: Vec::new(),
+        }
+    }
+}
+
+impl Parser {
+    /// A parser with [`default_mapping`].
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Replaces the mapping.
+    pub fn mapping(mut self, mapping: Mapping) -> Self {
+        self.mapping = mapping;
+        self
+    }
+
+    /// Our own networks, like `HOME_NET`. Without any, private addresses
+    /// are considered internal.
+    pub fn home_network(mut self, network: IpNetwork) -> Self {
+        self.home_networks.push(network);
+        self
+    }
+
+    fn is_internal(&self, address: &IpAddr) -> bool {
+        if self.home_networks.is_empty() {
+            is_private(address)
+        } else {
+            self.home_networks.iter().any(|n| n.contains(*address))
+        }
+    }
+
+    /// Parses one EVE JSON line. Returns `None` for non-alerts, unmapped
+    /// alerts and flows without an external endpoint.
+    pub fn parse_line(&self, line: &str) -> Option<Event> {
+        let record: EveRecord = serde_json::from_str(line).ok()?;
+        if record.event_type != "alert" {
+            return None;
+        }
+        let alert = record.alert?;
+
+        let categories = self.mapping.categories(&alert.category, &alert.signature);
+        if categories.is_empty() {
+            return None;
+        }
+
+        let address = if !self.is_internal(&record.src_ip) {
+            record.src_ip
+        } else if !self.is_internal(&record.dest_ip) {
+            record.dest_ip
+        } else {
+            return None;
+        };
+
+        let timestamp = DateTime::parse_from_str(&record.timestamp, "%Y-%m-%dT%H:%M:%S%.f%z")
+            .or_else(|_| DateTime::parse_from_rfc3339(&record.timestamp))
+            .map(|t| t.to_utc())
+            .unwrap_or_else(|_| Utc::now());
+
+        let mut evidence = format!(
+            "Suricata: {} [{}:{}:{}]",
+            alert.signature, alert.gid, alert.signature_id, alert.rev
+        );
+        if let (Some(proto), Some(port)) = (&record.proto, record.dest_port) {
+            evidence.push_str(&format!(" {proto}/{port}"));
+        }
+
+        Some(
+            Event::new(address, &categories, "IDS alert", evidence, timestamp)
+                .with_target("signature", alert.signature_id),
+        )
+    }
+}
+
+/// Follows an `eve.json` file from its current end, calling `on_event` for
+/// every mapped alert. Starts over on a new file when it is rotated, or at
+/// the beginning when it is truncated.
+pub async fn tail<P, F>(path: P, parser: &Parser, mut on_event: F) -> crate::Result<()>
+where
+    P: AsRef<Path>,
+    F: FnMut(Event),
+{
+    let path = path.as_ref();
+    let mut file = tokio::fs::File::open(path).await?;
+    let mut current = inode(&file.metadata().await?);
+    let mut position = file.seek(SeekFrom::End(0)).await?;
+    let mut reader = BufReader::new(file);
+    let mut line = String::new();
+
+    loop {
+        let read = reader.read_line(&mut line).await?;
+        position += read as u64;
+        if line.ends_with('\n') {
+            if let Some(event) = parser.parse_line(&line) {
+                on_event(event);
+            }
+            line.clear();
+            continue;
+        }
+        if read > 0 {
+            continue;
+        }
+
+        tokio::time::sleep(Duration::from_millis(250)).await;
+
+        let Ok(metadata) = tokio::fs::metadata(path).await else {
+            // rotated away and not yet recreated
+            continue;
+        };
+        // renamed (the old file is drained above) or truncated
+        if inode(&metadata) != current || metadata.len() < position {
+            file = tokio::fs::File::open(path).await?;
+            current = inode(&file.metadata().await?);
+            reader = BufReader::new(file);
+            position = 0;
+            line.clear();
+        }
+    }
+}
@@ -0,0 +1,316 @@
+use std::{
+    net::{IpAddr, SocketAddr},
+    sync::{Arc, OnceLock},
+};
+
+use chrono::{DateTime, FixedOffset, Utc};
+use regex::Regex;
+use tokio::{
+    io::{AsyncBufReadExt, AsyncReadExt, BufReader},
+    net::{TcpListener, TcpStream, UdpSocket},
+    sync::mpsc,
+};
+
+use crate::{
+    endpoints::report::Request, event::Event, ingest::LogLine, sanitize::Sanitizer,
+    types::Category, Client,
+};
+
+/// Longest message accepted over TCP, larger frames close the connection.
+const MAX_MESSAGE_LENGTH: usize = 64 * 1024;
+
+fn rfc5424_regex() -> &'static Regex {
+    static REGEX: OnceLock<Regex> = OnceLock::new();
+    REGEX.get_or_init(|| {
+        Regex::new(
+            r#"^1 (?P<timestamp>\S+) (?P<host>\S+) (?P<app>\S+) (?P<pid>\S+) \S+ (?:-|(?:\[(?:[^\]"\\]|"(?:[^"\\]|\\.)*")*\])+)(?: (?P<message>.*))?$"#,
+        )
+        .unwrap()
+    })
+}
+
+/// RFC 3164 without the optional HOSTNAME, as many appliances send it.
+fn rfc3164_hostless_regex() -> &'static Regex {
+    static REGEX: OnceLock<Regex> = OnceLock::new();
+    REGEX.get_or_init(|| {
+        Regex::new(
+            r"^(?P<timestamp>[A-Z][a-z]{2} [ \d]\d \d{2}:\d{2}:\d{2}) (?P<program>[^\s\[:]+)(?:\[(?P<pid>\d+)\])?:\s?(?P<message>.*)$",
+        )
+        .unwrap()
+    })
+}
+
+fn nil(value: &str) -> Option<&str> {
+    Some(value).filter(|v| *v != "-")
+}
+
+/// Parses an RFC 5424 or RFC 3164 message as received from the network.
+/// RFC 3164 timestamps are interpreted in `offset`, and messages without a
+/// hostname get an empty host.
+pub fn parse_message(message: &str, offset: FixedOffset, now: DateTime<Utc>) -> Option<LogLine> {
+    let message = message.trim_end_matches(['\r', '\n', '\0']);
+    let rest = message.strip_prefix('<')?;
+    let (priority, rest) = rest.split_once('>')?;
+    // facility 23 at severity 7 is the highest priority
+    if priority.is_empty() || priority.len() > 3 || !priority.parse::<u8>().is_ok_and(|p| p <= 191)
+    {
+        return None;
+    }
+
+    if let Some(caps) = rfc5424_regex().captures(rest) {
+        let timestamp = nil(&caps["timestamp"])
+            .and_then(|t| DateTime::parse_from_rfc3339(t).ok())
+            .map_or(now, |t| t.to_utc());
+        let text = caps.name("message").map_or("", |m| m.as_str());
+
+        return Some(LogLine {
+            timestamp,
+            host: nil(&caps["host"]).unwrap_or_default().to_string(),
+            program: nil(&caps["app"]).unwrap_or_default().to_string(),
+            pid: nil(&caps["pid"]).and_then(|p| p.parse().ok()),
+            message: text.trim_start_matches('\u{feff}').to_string(),
+        });
+    }
+
+    // a tag right after the timestamp can't be a hostname, which would
+    // otherwise swallow it
+    if let Some(caps) = rfc3164_hostless_regex().captures(rest) {
+        return Some(LogLine {
+            timestamp: super::parse_bsd_timestamp(&caps["timestamp"], offset, now)?,
+            host: String::new(),
+            program: caps["program"].to_string(),
+            pid: caps.name("pid").and_then(|p| p.as_str().parse().ok()),
+            message: caps["message"].to_string(),
+        });
+    }
+
+    super::parse_syslog_line(rest, offset, now)
+}
+
+/// Maps messages of an app-name that match a pattern to categories. The
+/// pattern must have an `ip` named group for the reported address.
+#[derive(Debug, Clone)]
+pub struct Rule {
+    /// Only messages from this app-name are matched, all if `None`.
+    pub app_name: Option<String>,
+    pub pattern: Regex,
+    pub categories: Vec<Category>,
+    /// Singular event kind, e.g. `"failed VPN login"`.
+    pub kind: String,
+}
+
+impl Rule {
+    pub fn new<T: ToString>(
+        app_name: Option<&str>,
+        pattern: &str,
+        categories: &[Category],
+        kind: T,
+    ) -> Result<Self, regex::Error> {
+        Ok(Self {
+            app_name: app_name.map(ToString::to_string),
+            pattern: Regex::new(pattern)?,
+            categories: categories.to_vec(),
+            kind: kind.to_string(),
+        })
+    }
+}
+
+/// Receives syslog over UDP and TCP and turns messages matching the
+/// configured rules into reports.
+#[derive(Debug, Clone)]
+pub struct Receiver {
+    rules: Vec<Rule>,
+    sanitizer: Sanitizer,
+    offset: FixedOffset,
+}
+
+impl Default for Receiver {
+    fn default() -> Self {
+        Self {
+            rules: Vec::new(),
+            sanitizer: Sanitizer::default(),
+            offset: FixedOffset::east_opt(0).unwrap(),
+        }
+    }
+}
+
+impl Receiver {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn rule(mut self, rule: Rule) -> Self {
+        self.rules.push(rule);
+        self
+    }
+
+    /// The sanitizer used to scrub evidence, defaults to [`Sanitizer::default`].
+    pub fn sanitizer(mut self, sanitizer: Sanitizer) -> Self {
+        self.sanitizer = sanitizer;
+        self
+    }
+
+    /// The zone of RFC 3164 timestamps, which carry none. Defaults to UTC.
+    pub fn offset(mut self, offset: FixedOffset) -> Self {
+        self.offset = offset;
+        self
+    }
+
+    /// Routes a parsed message to the first matching rule.
+    pub fn match_line(&self, line: &LogLine) -> Option<Event> {
+        for rule in &self.rules {
+            if rule
+                .app_name
+                .as_ref()
+                .is_some_and(|app| *app != line.program)
+            {
+                continue;
+            }
+            let Some(caps) = rule.pattern.captures(&line.message) else {
+                continue;
+            };
+            let Some(address) = caps
+                .name("ip")
+                .and_then(|ip| ip.as_str().parse::<IpAddr>().ok())
+            else {
+                continue;
+            };
+
+            let evidence = self
+                .sanitizer
+                .sanitize(&format!("{}: {}", line.program, line.message));
+            return Some(Event::new(
+                address,
+                &rule.categories,
+                &rule.kind,
+                evidence,
+                line.timestamp,
+            ));
+        }
+        None
+    }
+
+    /// Parses and routes a raw message.
+    pub fn handle(&self, message: &str) -> Option<Event> {
+        let line = parse_message(message, self.offset, Utc::now())?;
+        self.match_line(&line)
+    }
+
+    /// Receives datagrams until the socket fails, one message each.
+    pub async fn serve_udp(
+        &self,
+        socket: UdpSocket,
+        events: mpsc::UnboundedSender<Event>,
+    ) -> crate::Result<()> {
+        let mut buffer = vec![0; MAX_MESSAGE_LENGTH];
+        loop {
+            let (length, _) = socket.recv_from(&mut buffer).await?;
+            let message = String::from_utf8_lossy(&buffer[..length]);
+            if let Some(event) = self.handle(&message) {
+                if events.send(event).is_err() {
+                    return Ok(());
+                }
+            }
+        }
+    }
+
+    async fn serve_connection(
+        &self,
+        stream: TcpStream,
+        events: mpsc::UnboundedSender<Event>,
+    ) -> std::io::Result<()> {
+        let mut reader = BufReader::new(stream);
+        let mut frame = Vec::new();
+        loop {
+            frame.clear();
+            let starts_with_digit = match reader.fill_buf().await?.first() {
+                None => return Ok(()),
+                Some(byte) => byte.is_ascii_digit(),
+            };
+
+            if starts_with_digit {
+                // octet counting (RFC 6587): "<length> <message>"
+                (&mut reader).take(7).read_until(b' ', &mut frame).await?;
+                let length: usize = frame
+                    .strip_suffix(b" ")
+                    .filter(|l| l.iter().all(u8::is_ascii_digit))
+                    .and_then(|l| std::str::from_utf8(l).ok())
+                    .and_then(|l| l.parse().ok())
+                    .filter(|l| *l <= MAX_MESSAGE_LENGTH)
+                    .ok_or(std::io::ErrorKind::InvalidData)?;
+                frame.resize(length, 0);
+                reader.read_exact(&mut frame).await?;
+            } else {
+                // non-transparent framing: one message per line
+                (&mut reader)
+                    .take(MAX_MESSAGE_LENGTH as u64 + 1)
+                    .read_until(b'\n', &mut frame)
+                    .await?;
+                if frame.len() > MAX_MESSAGE_LENGTH && !frame.ends_with(b"\n") {
+                    return Err(std::io::ErrorKind::InvalidData.into());
+                }
+            }
+
+            if let Some(event) = self.handle(&String::from_utf8_lossy(&frame)) {
+                if events.send(event).is_err() {
+                    return Ok(());
+                }
+            }
+        }
+    }
+
+    /// Accepts connections until the listener fails, serving each in its
+    /// own task.
+    pub async fn serve_tcp(
+        self: Arc<Self>,
+        listener: TcpListener,
+        events: mpsc::UnboundedSender<Event>,
+    ) -> crate::Result<()> {
+        loop {
+            let (stream, _) = listener.accept().await?;
+            let receiver = self.clone();
+            let events = events.clone();
+            tokio::spawn(async move {
+                // a broken connection only affects its own sender
+                let _ = receiver.serve_connection(stream, events).await;
+            });
+        }
+    }
+
+    /// Listens on the given addresses and submits every match through
+    /// [`Client::send_report`]. Failed submissions are passed to
+    /// `on_error`.
+    pub async fn run<F>(
+        self,
+        client: &Client,
+        udp: Option<SocketAddr>,
+        tcp: Option<SocketAddr>,
+        mut on_error: F,
+    ) -> crate::Result<()>
+    where
+        F: FnMut(Request, crate::Error),
+    {
+        let receiver = Arc::new(self);
+        let (sender, mut events) = mpsc::unbounded_channel();
+
+        if let Some(address) = udp {
+            let socket = UdpSocket::bind(address).await?;
+            let receiver = receiver.clone();
+            let sender = sender.clone();
+            tokio::spawn(async move { receiver.serve_udp(socket, sender).await });
+        }
+        if let Some(address) = tcp {
+            let listener = TcpListener::bind(address).await?;
+            tokio::spawn(receiver.serve_tcp(listener, sender.clone()));
+        }
+        drop(sender);
+
+        while let Some(event) = events.recv().await {
+            let request = event.to_request();
+            if let Err(e) = client.send_report(&request).await {
+                on_error(request, e);
+            }
+        }
+        Ok(())
+    }
+}
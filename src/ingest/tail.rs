@@ -0,0 +1,290 @@
+use std::{
+    collections::HashMap,
+    fs::{self, File, Metadata},
+    io::{BufRead, BufReader, Seek, SeekFrom},
+    path::{Path, PathBuf},
+    time::Duration,
+};
+
+use serde::{Deserialize, Serialize};
+
+use crate::{endpoints::report::Request, Client};
+
+/// Turns a log line into a report, or `None` if the line is not abuse.
+pub type Matcher = Box<dyn FnMut(&str) -> Option<Request> + Send>;
+
+#[cfg(unix)]
+pub(crate) fn inode(metadata: &Metadata) -> u64 {
+    std::os::unix::fs::MetadataExt::ino(metadata)
+}
+
+#[cfg(not(unix))]
+pub(crate) fn inode(_metadata: &Metadata) -> u64 {
+    0
+}
+
+#[derive(Debug, Clone, Copy, Default, Serialize, Deserialize)]
+struct Checkpoint {
+    inode: u64,
+    offset: u64,
+}
+
+#[derive(Debug)]
+struct Watched {
+    path: PathBuf,
+    reader: Option<BufReader<File>>,
+    checkpoint: Checkpoint,
+    partial: Vec<u8>,
+}
+
+impl Watched {
+    /// Reads complete lines from the open file, keeping a trailing partial
+    /// line for the next call. Invalid UTF-8 is replaced, the offset always
+    /// advances by the bytes read.
+    fn read_lines(&mut self, lines: &mut Vec<String>) -> std::io::Result<()> {
+        let Some(reader) = &mut self.reader else {
+            return Ok(());
+        };
+        loop {
+            let read = reader.read_until(b'\n', &mut self.partial)?;
+            if read == 0 {
+                return Ok(());
+            }
+            if self.partial.ends_with(b"\n") {
+                self.checkpoint.offset += self.partial.len() as u64;
+                lines.push(
+                    String::from_utf8_lossy(&self.partial)
+                        .trim_end()
+                        .to_string(),
+                );
+                self.partial.clear();
+            }
+        }
+    }
+
+    fn open(&mut self, metadata: &Metadata, offset: u64) -> std::io::Result<()> {
+        let mut file = File::open(&self.path)?;
+        file.seek(SeekFrom::Start(offset))?;
+        self.reader = Some(BufReader::new(file));
+        self.checkpoint = Checkpoint {
+            inode: inode(metadata),
+            offset,
+        };
+        self.partial.clear();
+        Ok(())
+    }
+}
+
+/// Follows log files, hands every line to the matchers and submits the
+/// resulting reports.
+///
+/// Handles logrotate renames (the old file is read to its end before the
+/// new one is opened) and truncation. Offsets and inodes are saved to a
+/// state file, so lines are neither skipped nor reported twice across
+/// restarts; a file rotated while we were down is finished from its `.1`
+/// sibling.
+pub struct Tailer {
+    files: Vec<Watched>,
+    matchers: Vec<Matcher>,
+    state_path: Option<PathBuf>,
+    from_start: bool,
+    interval: Duration,
+    started: bool,
+}
+
+impl Default for Tailer {
+    fn default() -> Self {
+        Self {
+            files: Vec::new(),
+            matchers: Vec::new(),
+            state_path: None,
+            from_start: false,
+            interval: Duration::from_secs(1),
+            started: false,
+        }
+    }
+}
+
+impl Tailer {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn file<P: AsRef<Path>>(mut self, path: P) -> Self {
+        self.files.push(Watched {
+            path: path.as_ref().to_path_buf(),
+            reader: None,
+            checkpoint: Checkpoint::default(),
+            partial: Vec::new(),
+        });
+        self
+    }
+
+    /// Adds a matcher. Every line is passed to every matcher.
+    pub fn matcher<F>(mut self, matcher: F) -> Self
+    where
+        F: FnMut(&str) -> Option<Request> + Send + 'static,
+    {
+        self.matchers.push(Box::new(matcher));
+        self
+    }
+
+    /// Where offsets are saved. Without one, restarts begin at the end of
+    /// each file.
+    pub fn state_file<P: AsRef<Path>>(mut self, path: P) -> Self {
+        self.state_path = Some(path.as_ref().to_path_buf());
+        self
+    }
+
+    /// Read files without a saved offset from the beginning instead of
+    /// the end.
+    pub fn from_start(mut self, from_start: bool) -> Self {
+        self.from_start = from_start;
+        self
+    }
+
+    /// How often files are polled, defaults to one second.
+    pub fn interval(mut self, interval: Duration) -> Self {
+        self.interval = interval;
+        self
+    }
+
+    fn load_state(&self) -> crate::Result<HashMap<PathBuf, Checkpoint>> {
+        let Some(path) = &self.state_path else {
+            return Ok(HashMap::new());
+        };
+        match fs::read(path) {
+            Ok(data) => Ok(serde_json::from_slice(&data)?),
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(HashMap::new()),
+            Err(e) => Err(e.into()),
+        }
+    }
+
+    fn save_state(&self) -> crate::Result<()> {
+        let Some(path) = &self.state_path else {
+            return Ok(());
+        };
+        let state: HashMap<&PathBuf, Checkpoint> = self
+            .files
+            .iter()
+            .filter(|w| w.reader.is_some())
+            .map(|w| (&w.path, w.checkpoint))
+            .collect();
+
+        let tmp = path.with_extension("tmp");
+        fs::write(&tmp, serde_json::to_vec(&state)?)?;
+        fs::rename(tmp, path)?;
+        Ok(())
+    }
+
+    /// Opens every file at its saved position, collecting lines left in a
+    /// file that was rotated while we were not running.
+    fn start(&mut self, lines: &mut Vec<String>) -> crate::Result<()> {
+        let state = self.load_state()?;
+        for watched in &mut self.files {
+            let Ok(metadata) = fs::metadata(&watched.path) else {
+                continue;
+            };
+            let saved = state.get(&watched.path);
+
+            match saved {
+                Some(saved) if saved.inode == inode(&metadata) => {
+                    let offset = if saved.offset <= metadata.len() {
+                        saved.offset
+                    } else {
+                        0
+                    };
+                    watched.open(&metadata, offset)?;
+                }
+                Some(saved) => {
+                    let mut rotated = watched.path.clone().into_os_string();
+                    rotated.push(".1");
+                    let rotated = PathBuf::from(rotated);
+                    if let Ok(old) = fs::metadata(&rotated) {
+                        if inode(&old) == saved.inode && saved.offset <= old.len() {
+                            let mut file = File::open(&rotated)?;
+                            file.seek(SeekFrom::Start(saved.offset))?;
+                            for line in BufReader::new(file).split(b'\n') {
+                                let line = line?;
+                                lines.push(String::from_utf8_lossy(&line).trim_end().to_string());
+                            }
+                        }
+                    }
+                    watched.open(&metadata, 0)?;
+                }
+                None if self.from_start => watched.open(&metadata, 0)?,
+                None => watched.open(&metadata, metadata.len())?,
+            }
+        }
+        self.started = true;
+        Ok(())
+    }
+
+    /// Reads new lines from all files and returns the reports produced by
+    /// the matchers. Offsets are saved before returning.
+    pub fn poll(&mut self) -> crate::Result<Vec<Request>> {
+        let mut lines = Vec::new();
+
+        if !self.started {
+            self.start(&mut lines)?;
+        }
+
+        for watched in &mut self.files {
+            watched.read_lines(&mut lines)?;
+
+            let Ok(metadata) = fs::metadata(&watched.path) else {
+                // rotated away and not yet recreated
+                continue;
+            };
+            if watched.reader.is_none() {
+                watched.open(&metadata, 0)?;
+            } else if inode(&metadata) != watched.checkpoint.inode {
+                // renamed by logrotate: the old file is drained above
+                watched.open(&metadata, 0)?;
+            } else if metadata.len() < watched.checkpoint.offset {
+                // truncated (copytruncate)
+                watched.open(&metadata, 0)?;
+            } else {
+                continue;
+            }
+            watched.read_lines(&mut lines)?;
+        }
+
+        let mut requests = Vec::new();
+        for line in &lines {
+            for matcher in &mut self.matchers {
+                if let Some(request) = matcher(line) {
+                    requests.push(request);
+                }
+            }
+        }
+
+        self.save_state()?;
+        Ok(requests)
+    }
+
+    /// Polls forever, submitting reports through [`Client::send_report`].
+    /// Failed submissions are passed to `on_error` and not retried; put a
+    /// [`crate::spool::Spool`] behind `on_error` to keep them. Errors while
+    /// reading the files are printed and polling goes on.
+    pub async fn run<F>(mut self, client: &Client, mut on_error: F) -> crate::Result<()>
+    where
+        F: FnMut(Request, crate::Error),
+    {
+        loop {
+            let requests = match self.poll() {
+                Ok(requests) => requests,
+                Err(e) => {
+                    eprintln!("abuseipdb2: polling log files failed: {e}");
+                    Vec::new()
+                }
+            };
+            for request in requests {
+                if let Err(e) = client.send_report(&request).await {
+                    on_error(request, e);
+                }
+            }
+            tokio::time::sleep(self.interval).await;
+        }
+    }
+}
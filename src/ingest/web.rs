@@ -0,0 +1,345 @@
+use std::{net::IpAddr, sync::OnceLock};
+
+use chrono::{DateTime, Utc};
+use ipnetwork::IpNetwork;
+use regex::Regex;
+use serde::Deserialize;
+
+use crate::{event::Event, sanitize::Sanitizer, types::Category};
+
+fn clf_regex() -> &'static Regex {
+    static REGEX: OnceLock<Regex> = OnceLock::new();
+    REGEX.get_or_init(|| {
+        Regex::new(
+            r#"^(?P<ip>\S+) \S+ \S+ \[(?P<time>[^\]]+)\] "(?P<request>(?:[^"\\]|\\.)*)" (?P<status>\d{3}|-) (?:\d+|-)(?: "(?P<referer>(?:[^"\\]|\\.)*)" "(?P<agent>(?:[^"\\]|\\.)*)")?(?: "(?P<forwarded>[^"]*)")?"#,
+        )
+        .unwrap()
+    })
+}
+
+/// Decodes `%XX` escapes and `+`, leaving invalid escapes as they are.
+fn percent_decode(input: &str) -> String {
+    let bytes = input.as_bytes();
+    let mut decoded = Vec::with_capacity(bytes.len());
+    let mut i = 0;
+    while i < bytes.len() {
+        let escaped = match bytes.get(i + 1..i + 3) {
+            Some(hex) if bytes[i] == b'%' => std::str::from_utf8(hex)
+                .ok()
+                .and_then(|hex| u8::from_str_radix(hex, 16).ok()),
+            _ => None,
+        };
+        match (escaped, bytes[i]) {
+            (Some(byte), _) => {
+                decoded.push(byte);
+                i += 3;
+                continue;
+            }
+            (None, b'+') => decoded.push(b' '),
+            (None, byte) => decoded.push(byte),
+        }
+        i += 1;
+    }
+    String::from_utf8_lossy(&decoded).into_owned()
+}
+
+/// One request from an access log.
+#[derive(Debug, Clone)]
+pub struct AccessEntry {
+    /// The peer address, usually the proxy if there is one.
+    pub remote: IpAddr,
+    pub timestamp: DateTime<Utc>,
+    pub method: String,
+    pub path: String,
+    pub status: Option<u16>,
+    pub user_agent: Option<String>,
+    /// Addresses from `X-Forwarded-For`, client first.
+    pub forwarded_for: Vec<IpAddr>,
+}
+
+fn parse_forwarded_for(value: &str) -> Vec<IpAddr> {
+    value
+        .split(',')
+        .filter_map(|part| part.trim().parse().ok())
+        .collect()
+}
+
+fn split_request(request: &str) -> (String, String) {
+    let mut parts = request.splitn(3, ' ');
+    match (parts.next(), parts.next()) {
+        (Some(method), Some(path)) => (method.to_string(), path.to_string()),
+        _ => (String::new(), request.to_string()),
+    }
+}
+
+/// Parses a line in common, combined or combined-plus-`X-Forwarded-For`
+/// log format.
+pub fn parse_clf(line: &str) -> Option<AccessEntry> {
+    let caps = clf_regex().captures(line.trim_end())?;
+    let timestamp = DateTime::parse_from_str(&caps["time"], "%d/%b/%Y:%H:%M:%S %z")
+        .ok()?
+        .to_utc();
+    let (method, path) = split_request(&caps["request"]);
+
+    Some(AccessEntry {
+        remote: caps["ip"].parse().ok()?,
+        timestamp,
+        method,
+        path,
+        status: caps["status"].parse().ok(),
+        user_agent: caps
+            .name("agent")
+            .map(|m| m.as_str().to_string())
+            .filter(|a| a != "-"),
+        forwarded_for: caps
+            .name("forwarded")
+            .map(|m| parse_forwarded_for(m.as_str()))
+            .unwrap_or_default(),
+    })
+}
+
+#[derive(Debug, Deserialize)]
+struct JsonEntry {
+    remote_addr: String,
+    time_iso8601: Option<String>,
+    time_local: Option<String>,
+    request: Option<String>,
+    request_method: Option<String>,
+    request_uri: Option<String>,
+    status: Option<serde_json::Value>,
+    http_user_agent: Option<String>,
+    http_x_forwarded_for: Option<String>,
+}
+
+/// Parses an nginx JSON log line using the standard variable names
+/// (`remote_addr`, `time_iso8601` or `time_local`, `request` or
+/// `request_method` and `request_uri`, `status`, `http_user_agent`,
+/// `http_x_forwarded_for`).
+pub fn parse_json(line: &str) -> Option<AccessEntry> {
+    let entry: JsonEntry = serde_json::from_str(line).ok()?;
+
+    let timestamp = match (&entry.time_iso8601, &entry.time_local) {
+        (Some(iso), _) => DateTime::parse_from_rfc3339(iso).ok()?.to_utc(),
+        (None, Some(local)) => DateTime::parse_from_str(local, "%d/%b/%Y:%H:%M:%S %z")
+            .ok()?
+            .to_utc(),
+        (None, None) => return None,
+    };
+
+    let (method, path) = match (entry.request_method, entry.request_uri, entry.request) {
+        (Some(method), Some(uri), _) => (method, uri),
+        (_, _, Some(request)) => split_request(&request),
+        _ => return None,
+    };
+
+    let status = match entry.status {
+        Some(serde_json::Value::Number(n)) => n.as_u64().and_then(|n| n.try_into().ok()),
+        Some(serde_json::Value::String(s)) => s.parse().ok(),
+        _ => None,
+    };
+
+    Some(AccessEntry {
+        remote: entry.remote_addr.parse().ok()?,
+        timestamp,
+        method,
+        path,
+        status,
+        user_agent: entry.http_user_agent.filter(|a| !a.is_empty() && a != "-"),
+        forwarded_for: entry
+            .http_x_forwarded_for
+            .as_deref()
+            .map(parse_forwarded_for)
+            .unwrap_or_default(),
+    })
+}
+
+/// Which part of a request a [`Rule`] is matched against.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Field {
+    /// The percent-decoded path and query.
+    Path,
+    UserAgent,
+}
+
+/// Flags requests whose field matches a pattern.
+#[derive(Debug, Clone)]
+pub struct Rule {
+    /// Singular event kind, e.g. `"web application probe"`.
+    pub kind: String,
+    pub field: Field,
+    pub pattern: Regex,
+    pub categories: Vec<Category>,
+}
+
+impl Rule {
+    pub fn new<T: ToString>(
+        kind: T,
+        field: Field,
+        pattern: &str,
+        categories: &[Category],
+    ) -> Result<Self, regex::Error> {
+        Ok(Self {
+            kind: kind.to_string(),
+            field,
+            pattern: Regex::new(pattern)?,
+            categories: categories.to_vec(),
+        })
+    }
+}
+
+/// The built-in rule set: CMS/admin/secret probes, SQL injection, path
+/// traversal and scanner user agents. `curl` and `wget` are left out, as
+/// health checks, uptime monitors and people use them too.
+pub fn default_rules() -> Vec<Rule> {
+    [
+        (
+            "web application probe",
+            Field::Path,
+            r"(?i)(?:wp-login\.php|xmlrpc\.php|/wp-admin|/wp-content/plugins/|phpmyadmin|/pma/|/\.env\b|/\.git/|/vendor/phpunit/|/boaform/|/HNAP1)",
+            &[Category::WebAppAttack][..],
+        ),
+        (
+            "SQL injection attempt",
+            Field::Path,
+            r"(?i)(?:union\s+(?:all\s+)?select|'\s*or\s+'?\d+'?\s*=\s*'?\d+|\bsleep\s*\(\s*\d+\s*\)|benchmark\s*\(|information_schema|;\s*drop\s+table)",
+            &[Category::SqlInjection, Category::WebAppAttack][..],
+        ),
+        (
+            "path traversal attempt",
+            Field::Path,
+            r"(?:\.\./|\.\.\\|/etc/passwd|/proc/self/environ|c:\\windows)",
+            &[Category::Hacking, Category::WebAppAttack][..],
+        ),
+        (
+            "bad bot request",
+            Field::UserAgent,
+            r"(?i)(?:python-requests|go-http-client|scrapy|libwww-perl|zgrab|masscan|nikto|sqlmap)",
+            &[Category::BadWebBot][..],
+        ),
+    ]
+    .into_iter()
+    .map(|(kind, field, pattern, categories)| {
+        Rule::new(kind, field, pattern, categories).unwrap()
+    })
+    .collect()
+}
+
+/// Classifies access log entries with a configurable rule set.
+#[derive(Debug, Clone)]
+pub struct Parser {
+    rules: Vec<Rule>,
+    trusted_proxies: Vec<IpNetwork>,
+    sanitizer: Sanitizer,
+}
+
+impl Default for Parser {
+    fn default() -> Self {
+        Self {
+            rules: default_rules(),
+            trusted_proxies: Vec::new(),
+            sanitizer: Sanitizer::default(),
+        }
+    }
+}
+
+impl Parser {
+    /// A parser with [`default_rules`].
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Replaces the rule set.
+    pub fn rules(mut self, rules: Vec<Rule>) -> Self {
+        self.rules = rules;
+        self
+    }
+
+    pub fn rule(mut self, rule: Rule) -> Self {
+        self.rules.push(rule);
+        self
+    }
+
+    /// `X-Forwarded-For` is only honored for requests from these networks.
+    pub fn trusted_proxy(mut self, network: IpNetwork) -> Self {
+        self.trusted_proxies.push(network);
+        self
+    }
+
+    /// The sanitizer used to scrub evidence, defaults to [`Sanitizer::default`].
+    pub fn sanitizer(mut self, sanitizer: Sanitizer) -> Self {
+        self.sanitizer = sanitizer;
+        self
+    }
+
+    fn is_trusted(&self, address: &IpAddr) -> bool {
+        self.trusted_proxies.iter().any(|n| n.contains(*address))
+    }
+
+    /// The client address: the peer, or when the peer is a trusted proxy,
+    /// the right-most `X-Forwarded-For` address that is not a trusted proxy.
+    pub fn client_address(&self, entry: &AccessEntry) -> IpAddr {
+        if !self.is_trusted(&entry.remote) {
+            return entry.remote;
+        }
+        entry
+            .forwarded_for
+            .iter()
+            .rev()
+            .find(|address| !self.is_trusted(address))
+            .copied()
+            .unwrap_or(entry.remote)
+    }
+
+    /// Matches an entry against the rules. All matching rules contribute
+    /// categories; the first one names the event.
+    pub fn classify(&self, entry: &AccessEntry) -> Option<Event> {
+        let path = percent_decode(&entry.path);
+        let mut matched: Option<(&str, Vec<Category>)> = None;
+
+        for rule in &self.rules {
+            let value = match rule.field {
+                Field::Path => path.as_str(),
+                Field::UserAgent => entry.user_agent.as_deref().unwrap_or_default(),
+            };
+            if !rule.pattern.is_match(value) {
+                continue;
+            }
+            let (_, categories) = matched.get_or_insert((&rule.kind, Vec::new()));
+            for category in &rule.categories {
+                if !categories.contains(category) {
+                    categories.push(*category);
+                }
+            }
+        }
+
+        let (kind, categories) = matched?;
+        let evidence = self.sanitizer.sanitize(&format!(
+            "\"{} {}\" {} \"{}\"",
+            entry.method,
+            entry.path,
+            entry.status.map_or("-".to_string(), |s| s.to_string()),
+            entry.user_agent.as_deref().unwrap_or("-"),
+        ));
+
+        Some(
+            Event::new(
+                self.client_address(entry),
+                &categories,
+                kind,
+                evidence,
+                entry.timestamp,
+            )
+            .with_target("path", &entry.path),
+        )
+    }
+
+    /// Parses and classifies a CLF or nginx JSON line.
+    pub fn parse_line(&self, line: &str) -> Option<Event> {
+        let entry = if line.trim_start().starts_with('{') {
+            parse_json(line)?
+        } else {
+            parse_clf(line)?
+        };
+        self.classify(&entry)
+    }
+}
@@ -0,0 +1,188 @@
+use std::{fmt, net::IpAddr, sync::Mutex};
+
+use chrono::{Duration, Utc};
+use tokio::sync::mpsc;
+use tracing::{
+    field::{Field, Visit},
+    Subscriber,
+};
+use tracing_subscriber::{layer::Context, Layer};
+
+use crate::{
+    dedup::{Deduplicator, Policy},
+    endpoints::report::Request,
+    sanitize::Sanitizer,
+    types::{parse_categories, Category},
+    Client,
+};
+
+/// The field holding the address to report.
+pub const CLIENT_IP_FIELD: &str = "client_ip";
+/// The field holding category ids (`18` or `"18,22"`) or names
+/// (`"SshAbuse"`).
+pub const CATEGORY_FIELD: &str = "abuse_category";
+
+/// Reports queued for submission before new ones are dropped.
+const QUEUE_LENGTH: usize = 1024;
+
+fn parse_category_names(s: &str) -> Option<Vec<Category>> {
+    parse_categories(s).or_else(|| {
+        s.split(',')
+            .map(str::trim)
+            .filter(|name| !name.is_empty())
+            .map(|name| {
+                (1..=u8::MAX)
+                    .map_while(|id| Category::try_from(id).ok())
+                    .find(|c| format!("{c:?}").eq_ignore_ascii_case(name))
+            })
+            .collect()
+    })
+}
+
+#[derive(Default)]
+struct Fields {
+    address: Option<IpAddr>,
+    categories: Vec<Category>,
+    message: String,
+}
+
+impl Fields {
+    fn set_categories(&mut self, value: &str) {
+        for category in parse_category_names(value).unwrap_or_default() {
+            if !self.categories.contains(&category) {
+                self.categories.push(category);
+            }
+        }
+    }
+}
+
+impl Visit for Fields {
+    fn record_str(&mut self, field: &Field, value: &str) {
+        match field.name() {
+            CLIENT_IP_FIELD => self.address = value.parse().ok(),
+            CATEGORY_FIELD => self.set_categories(value),
+            "message" => self.message = value.to_string(),
+            _ => {}
+        }
+    }
+
+    fn record_u64(&mut self, field: &Field, value: u64) {
+        if field.name() == CATEGORY_FIELD {
+            self.set_categories(&value.to_string());
+        }
+    }
+
+    fn record_i64(&mut self, field: &Field, value: i64) {
+        self.record_u64(field, value.max(0) as u64);
+    }
+
+    fn record_debug(&mut self, field: &Field, value: &dyn fmt::Debug) {
+        let value = format!("{value:?}");
+        self.record_str(field, value.trim_matches('"'));
+    }
+}
+
+/// A [`Layer`] that reports events carrying a [`CLIENT_IP_FIELD`] and a
+/// [`CATEGORY_FIELD`], with the event message as comment:
+///
+/// ```ignore
+/// warn!(client_ip = %addr, abuse_category = "18,21", "wp-login.php brute force");
+/// ```
+///
+/// Each address is reported at most once per window; repeats are dropped.
+/// Reports are submitted by the [`Worker`] returned alongside the layer.
+pub struct ReportLayer {
+    dedup: Mutex<Deduplicator>,
+    sanitizer: Sanitizer,
+    queue: mpsc::Sender<Request>,
+}
+
+/// Submits the reports picked out by a [`ReportLayer`].
+pub struct Worker {
+    client: Client,
+    queue: mpsc::Receiver<Request>,
+}
+
+impl ReportLayer {
+    /// A layer and the worker that submits its reports; spawn
+    /// [`Worker::run`] on the runtime.
+    pub fn new(client: Client) -> (Self, Worker) {
+        let (sender, receiver) = mpsc::channel(QUEUE_LENGTH);
+        let layer = Self {
+            dedup: Mutex::new(Deduplicator::new(Policy::Skip)),
+            sanitizer: Sanitizer::default(),
+            queue: sender,
+        };
+        let worker = Worker {
+            client,
+            queue: receiver,
+        };
+        (layer, worker)
+    }
+
+    /// How long an address is not reported again, defaults to
+    /// [`crate::dedup::REPORT_WINDOW`].
+    pub fn window(self, window: Duration) -> Self {
+        let dedup = self.dedup.into_inner().unwrap().with_window(window);
+        Self {
+            dedup: Mutex::new(dedup),
+            ..self
+        }
+    }
+
+    /// The sanitizer used to scrub messages, defaults to
+    /// [`Sanitizer::default`].
+    pub fn sanitizer(mut self, sanitizer: Sanitizer) -> Self {
+        self.sanitizer = sanitizer;
+        self
+    }
+}
+
+impl<S: Subscriber> Layer<S> for ReportLayer {
+    fn on_event(&self, event: &tracing::Event<'_>, _ctx: Context<'_, S>) {
+        if event.metadata().fields().field(CLIENT_IP_FIELD).is_none() {
+            return;
+        }
+
+        let mut visitor = Fields::default();
+        event.record(&mut visitor);
+        let Some(address) = visitor.address else {
+            return;
+        };
+        if visitor.categories.is_empty() {
+            return;
+        }
+
+        let mut request = Request::new(address, visitor.categories);
+        let comment = self.sanitizer.sanitize(&visitor.message);
+        if !comment.is_empty() {
+            request = request.with_comment(comment);
+        }
+
+        let now = Utc::now();
+        let mut dedup = self.dedup.lock().unwrap();
+        if dedup.check(&request, now).is_some() {
+            return;
+        }
+        // only remember addresses that were actually queued
+        if self.queue.try_send(request.clone()).is_ok() {
+            dedup.prune(now);
+            dedup.record(&request, now);
+        }
+    }
+}
+
+impl Worker {
+    /// Submits reports through [`Client::send_report`] until the layer is
+    /// dropped. Failed submissions are passed to `on_error`.
+    pub async fn run<F>(mut self, mut on_error: F)
+    where
+        F: FnMut(Request, crate::Error),
+    {
+        while let Some(request) = self.queue.recv().await {
+            if let Err(e) = self.client.send_report(&request).await {
+                on_error(request, e);
+            }
+        }
+    }
+}
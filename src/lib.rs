@@ -4,7 +4,22 @@ use serde::{Deserialize, Serialize};
 use thiserror::Error;
 use url::Url;
 
+pub mod aggregate;
+pub mod cidr;
+pub mod country;
+pub mod dedup;
 pub mod endpoints;
+pub mod event;
+pub mod export;
+pub mod honeypot;
+pub mod ingest;
+#[cfg(feature = "tracing")]
+pub mod layer;
+pub mod lookup;
+pub mod mapped;
+pub mod sanitize;
+pub mod snapshot;
+pub mod spool;
 pub mod types;
 
 const DEFAULT_BASE_URL: &str = "https://api.abuseipdb.com/api/v2/";
@@ -20,12 +35,39 @@ pub enum Error {
     },
     #[error("HTTP error: {0}")]
     Http(#[from] reqwest::Error),
+    #[error("IO error: {0}")]
+    Io(#[from] std::io::Error),
+    #[error("JSON error: {0}")]
+    Json(#[from] serde_json::Error),
     #[error("API errors: {0:?}")]
     Other(Vec<types::Error>),
+    /// An error response without the usual JSON error body, or any server
+    /// error.
+    #[error("HTTP status: {0}")]
+    Status(reqwest::StatusCode),
+    #[error("Invalid country code: {0:?}")]
+    InvalidCountryCode(String),
+    #[error("Invalid snapshot file: {0}")]
+    InvalidSnapshot(String),
+}
+
+impl Error {
+    /// Whether the request may succeed later: connection failures, timeouts,
+    /// rate limits and server errors, as opposed to requests the API
+    /// rejected or responses we could not decode.
+    pub fn is_retryable(&self) -> bool {
+        match self {
+            Error::Http(e) => e.is_connect() || e.is_timeout(),
+            Error::RateLimit { .. } => true,
+            Error::Status(status) => status.is_server_error(),
+            _ => false,
+        }
+    }
 }
 
 pub type Result<T> = std::result::Result<T, Error>;
 
+#[derive(Clone)]
 pub struct Client {
     http: reqwest::Client,
     base: Url,
@@ -37,7 +79,6 @@ impl Client {
         Self::new_with_base(key, DEFAULT_BASE_URL).unwrap()
     }
 
-    #[must_use]
     pub fn new_with_base<TK: ToString, TB: IntoUrl>(key: TK, base_url: TB) -> Result<Self> {
         let mut headers = HeaderMap::new();
         headers.insert("key", key.to_string().parse().unwrap());
@@ -89,7 +130,7 @@ impl Client {
             .and_then(|v| v.to_str().ok())
             .and_then(|v| v.parse().ok())
             .map(|v: i64| DateTime::from_timestamp(v, 0).unwrap())
-            .unwrap_or_else(|| Utc::now());
+            .unwrap_or_else(Utc::now);
 
         Error::RateLimit {
             retry_after,
@@ -100,15 +141,20 @@ impl Client {
     }
 
     async fn make_error(&self, response: reqwest::Response) -> Error {
-        if response.status() == reqwest::StatusCode::TOO_MANY_REQUESTS {
+        let status = response.status();
+        if status == reqwest::StatusCode::TOO_MANY_REQUESTS {
             return self.make_ratelimit_error(response.headers());
-        } else {
-            #[derive(Debug, Deserialize)]
-            struct Response {
-                errors: Vec<types::Error>,
-            }
-            let response = response.json::<Response>().await.unwrap();
-            Error::Other(response.errors)
+        }
+
+        #[derive(Debug, Deserialize)]
+        struct Response {
+            errors: Vec<types::Error>,
+        }
+        // server errors stay retryable whatever the body, e.g. an HTML page
+        // from a proxy
+        match response.json::<Response>().await {
+            Ok(response) if !status.is_server_error() => Error::Other(response.errors),
+            _ => Error::Status(status),
         }
     }
 
@@ -148,4 +194,18 @@ impl Client {
             Err(self.make_error(response).await)
         }
     }
+
+    async fn post_multipart<TU, TO>(&self, url: TU, form: reqwest::multipart::Form) -> Result<TO>
+    where
+        TU: IntoUrl,
+        TO: for<'de> Deserialize<'de>,
+    {
+        let response = self.http.post(url).multipart(form).send().await?;
+
+        if response.status().is_success() {
+            Ok(response.json().await?)
+        } else {
+            Err(self.make_error(response).await)
+        }
+    }
 }
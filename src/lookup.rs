@@ -0,0 +1,233 @@
+use std::{
+    collections::BinaryHeap,
+    net::{IpAddr, Ipv4Addr, Ipv6Addr},
+    sync::Arc,
+};
+
+use arc_swap::{ArcSwap, Guard};
+use ipnetwork::IpNetwork;
+
+use crate::{endpoints::blacklist::Response, snapshot::Snapshot};
+
+/// Disjoint sorted ranges with a score each, searched by binary search over
+/// the range starts.
+#[derive(Debug, Clone, Default)]
+struct Ranges<T> {
+    starts: Vec<T>,
+    ends: Vec<T>,
+    scores: Vec<u32>,
+}
+
+impl<T: Copy + Ord> Ranges<T> {
+    fn get(&self, value: T) -> Option<u32> {
+        let index = self.starts.partition_point(|start| *start <= value);
+        let index = index.checked_sub(1)?;
+        (value <= self.ends[index]).then(|| self.scores[index])
+    }
+}
+
+/// Splits possibly overlapping `(start, end, score)` intervals into
+/// disjoint ranges with the highest score covering each, merging
+/// neighbours with the same score.
+fn flatten(mut intervals: Vec<(u128, u128, u32)>) -> Vec<(u128, u128, u32)> {
+    intervals.sort_unstable_by_key(|(start, ..)| *start);
+
+    let mut ranges: Vec<(u128, u128, u32)> = Vec::new();
+    let mut active = BinaryHeap::new();
+    let mut next = 0;
+    let mut position = 0;
+    loop {
+        if active.is_empty() {
+            let Some((start, ..)) = intervals.get(next) else {
+                break;
+            };
+            position = *start;
+        }
+        while let Some((start, end, score)) = intervals.get(next) {
+            if *start > position {
+                break;
+            }
+            active.push((*score, *end));
+            next += 1;
+        }
+        while active.peek().is_some_and(|(_, end)| *end < position) {
+            active.pop();
+        }
+        let Some((score, end)) = active.peek().copied() else {
+            continue;
+        };
+
+        // the top interval holds until it ends or another one starts
+        let mut until = end;
+        if let Some((start, ..)) = intervals.get(next) {
+            until = until.min(start - 1);
+        }
+        match ranges.last_mut() {
+            Some(last) if last.2 == score && last.1.checked_add(1) == Some(position) => {
+                last.1 = until
+            }
+            _ => ranges.push((position, until, score)),
+        }
+
+        let Some(after) = until.checked_add(1) else {
+            break;
+        };
+        position = after;
+        while active.peek().is_some_and(|(_, end)| *end < position) {
+            active.pop();
+        }
+    }
+    ranges
+}
+
+/// Collects addresses and networks for a [`Lookup`].
+#[derive(Debug, Clone, Default)]
+pub struct Builder {
+    v4: Vec<(u128, u128, u32)>,
+    v6: Vec<(u128, u128, u32)>,
+}
+
+impl Builder {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn address(self, address: IpAddr, score: u32) -> Self {
+        self.network(address.into(), score)
+    }
+
+    /// Adds a network, e.g. a range blocked by our own policy. Where
+    /// entries overlap, the highest score wins.
+    pub fn network(mut self, network: IpNetwork, score: u32) -> Self {
+        match network {
+            IpNetwork::V4(network) => self.v4.push((
+                u32::from(network.network()) as u128,
+                u32::from(network.broadcast()) as u128,
+                score,
+            )),
+            IpNetwork::V6(network) => {
+                let start = u128::from(network.network());
+                let end = start | u128::MAX.checked_shr(network.prefix() as u32).unwrap_or(0);
+                self.v6.push((start, end, score));
+            }
+        }
+        self
+    }
+
+    pub fn snapshot(mut self, snapshot: &Snapshot) -> Self {
+        for (address, score) in &snapshot.entries {
+            self = self.address(*address, *score);
+        }
+        self
+    }
+
+    pub fn blacklist(mut self, response: &Response) -> Self {
+        for entry in response.entries() {
+            self = self.address(entry.address(), entry.abuse_confidence_score());
+        }
+        self
+    }
+
+    pub fn build(self) -> Lookup {
+        let mut lookup = Lookup::default();
+        for (start, end, score) in flatten(self.v4) {
+            lookup.v4.starts.push(start as u32);
+            lookup.v4.ends.push(end as u32);
+            lookup.v4.scores.push(score);
+        }
+        for (start, end, score) in flatten(self.v6) {
+            lookup.v6.starts.push(start);
+            lookup.v6.ends.push(end);
+            lookup.v6.scores.push(score);
+        }
+        lookup
+    }
+}
+
+/// An immutable set of addresses and networks with their scores, answering
+/// lookups with a binary search over disjoint ranges.
+#[derive(Debug, Clone, Default)]
+pub struct Lookup {
+    v4: Ranges<u32>,
+    v6: Ranges<u128>,
+}
+
+impl Lookup {
+    pub fn builder() -> Builder {
+        Builder::new()
+    }
+
+    pub fn from_snapshot(snapshot: &Snapshot) -> Self {
+        Builder::new().snapshot(snapshot).build()
+    }
+
+    /// The score of the listed address or network containing `address`.
+    /// IPv4-mapped IPv6 addresses are looked up as IPv4.
+    pub fn get(&self, address: IpAddr) -> Option<u32> {
+        match address.to_canonical() {
+            IpAddr::V4(address) => self.get_v4(address),
+            IpAddr::V6(address) => self.get_v6(address),
+        }
+    }
+
+    pub fn get_v4(&self, address: Ipv4Addr) -> Option<u32> {
+        self.v4.get(u32::from(address))
+    }
+
+    pub fn get_v6(&self, address: Ipv6Addr) -> Option<u32> {
+        self.v6.get(u128::from(address))
+    }
+
+    pub fn contains(&self, address: IpAddr) -> bool {
+        self.get(address).is_some()
+    }
+
+    /// The number of disjoint ranges.
+    pub fn len(&self) -> usize {
+        self.v4.starts.len() + self.v6.starts.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+}
+
+/// A [`Lookup`] shared between threads that can be replaced while readers
+/// use it. Reads and replacements are lock-free; readers holding the old
+/// lookup keep it alive until they are done.
+#[derive(Debug)]
+pub struct SharedLookup {
+    current: ArcSwap<Lookup>,
+}
+
+impl Default for SharedLookup {
+    fn default() -> Self {
+        Self::new(Lookup::default())
+    }
+}
+
+impl SharedLookup {
+    pub fn new(lookup: Lookup) -> Self {
+        Self {
+            current: ArcSwap::from_pointee(lookup),
+        }
+    }
+
+    /// The current lookup, for several lookups against the same version.
+    pub fn load(&self) -> Guard<Arc<Lookup>> {
+        self.current.load()
+    }
+
+    pub fn get(&self, address: IpAddr) -> Option<u32> {
+        self.current.load().get(address)
+    }
+
+    pub fn contains(&self, address: IpAddr) -> bool {
+        self.get(address).is_some()
+    }
+
+    /// Replaces the lookup, e.g. after a new blacklist download.
+    pub fn replace(&self, lookup: Lookup) {
+        self.current.store(Arc::new(lookup));
+    }
+}
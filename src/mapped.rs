@@ -0,0 +1,328 @@
+use std::{
+    cmp::Ordering,
+    fs::{self, File},
+    net::{IpAddr, Ipv4Addr, Ipv6Addr},
+    path::Path,
+};
+
+use chrono::{DateTime, Utc};
+use memmap2::Mmap;
+
+use crate::{endpoints::blacklist::Response, snapshot::Snapshot, Error};
+
+// Layout, all integers little endian except addresses:
+//
+// header (64 bytes)
+//   0  magic            8 bytes
+//   8  version          u32
+//   12 flags            u32, reserved
+//   16 generated at     i64, Unix seconds
+//   24 IPv4 records     u64
+//   32 IPv6 records     u64
+//   40 checksum         u64, FNV-1a of every other byte of the file
+//   48 reserved         16 bytes
+// IPv4 records (16 bytes each), sorted by address
+//   0  address          4 bytes, big endian
+//   4  score            u32
+//   8  last reported    i64, Unix seconds
+// IPv6 records (32 bytes each), sorted by address
+//   0  address          16 bytes, big endian
+//   16 score            u32
+//   20 reserved         u32
+//   24 last reported    i64, Unix seconds
+//
+// Unknown dates are `i64::MIN`.
+
+/// The first bytes of a binary snapshot.
+pub const MAGIC: [u8; 8] = *b"AIPDBSNP";
+/// The format version written by [`encode`].
+pub const VERSION: u32 = 1;
+
+const HEADER_LEN: usize = 64;
+const V4_RECORD_LEN: usize = 16;
+const V6_RECORD_LEN: usize = 32;
+const UNKNOWN_DATE: i64 = i64::MIN;
+
+fn invalid<T>(reason: &str) -> crate::Result<T> {
+    Err(Error::InvalidSnapshot(reason.to_string()))
+}
+
+fn fnv1a(hash: u64, data: &[u8]) -> u64 {
+    data.iter().fold(hash, |hash, byte| {
+        (hash ^ *byte as u64).wrapping_mul(0x0100_0000_01b3)
+    })
+}
+
+fn checksum(data: &[u8]) -> u64 {
+    [&data[..40], &data[48..]]
+        .into_iter()
+        .fold(0xcbf2_9ce4_8422_2325, fnv1a)
+}
+
+fn to_timestamp(date: Option<DateTime<Utc>>) -> i64 {
+    date.map_or(UNKNOWN_DATE, |date| date.timestamp())
+}
+
+fn from_timestamp(timestamp: i64) -> Option<DateTime<Utc>> {
+    if timestamp == UNKNOWN_DATE {
+        None
+    } else {
+        DateTime::from_timestamp(timestamp, 0)
+    }
+}
+
+fn read_u32(data: &[u8], offset: usize) -> u32 {
+    u32::from_le_bytes(data[offset..offset + 4].try_into().unwrap())
+}
+
+fn read_u64(data: &[u8], offset: usize) -> u64 {
+    u64::from_le_bytes(data[offset..offset + 8].try_into().unwrap())
+}
+
+fn read_i64(data: &[u8], offset: usize) -> i64 {
+    i64::from_le_bytes(data[offset..offset + 8].try_into().unwrap())
+}
+
+/// A listed address as stored in a binary snapshot.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Record {
+    pub address: IpAddr,
+    pub score: u32,
+    pub last_reported_at: Option<DateTime<Utc>>,
+}
+
+impl Record {
+    fn write(&self, data: &mut Vec<u8>) {
+        let last_reported_at = to_timestamp(self.last_reported_at).to_le_bytes();
+        match self.address {
+            IpAddr::V4(address) => {
+                data.extend_from_slice(&address.octets());
+                data.extend_from_slice(&self.score.to_le_bytes());
+                data.extend_from_slice(&last_reported_at);
+            }
+            IpAddr::V6(address) => {
+                data.extend_from_slice(&address.octets());
+                data.extend_from_slice(&self.score.to_le_bytes());
+                data.extend_from_slice(&[0; 4]);
+                data.extend_from_slice(&last_reported_at);
+            }
+        }
+    }
+
+    fn read_v4(record: &[u8]) -> Self {
+        let octets: [u8; 4] = record[..4].try_into().unwrap();
+        Self {
+            address: IpAddr::V4(Ipv4Addr::from(octets)),
+            score: read_u32(record, 4),
+            last_reported_at: from_timestamp(read_i64(record, 8)),
+        }
+    }
+
+    fn read_v6(record: &[u8]) -> Self {
+        let octets: [u8; 16] = record[..16].try_into().unwrap();
+        Self {
+            address: IpAddr::V6(Ipv6Addr::from(octets)),
+            score: read_u32(record, 16),
+            last_reported_at: from_timestamp(read_i64(record, 24)),
+        }
+    }
+}
+
+/// Encodes records as a binary snapshot. Addresses listed more than once
+/// keep the record with the highest score.
+pub fn encode<I: IntoIterator<Item = Record>>(
+    generated_at: Option<DateTime<Utc>>,
+    records: I,
+) -> Vec<u8> {
+    let mut records: Vec<Record> = records
+        .into_iter()
+        .map(|record| Record {
+            address: record.address.to_canonical(),
+            ..record
+        })
+        .collect();
+    records.sort_unstable_by(|a, b| a.address.cmp(&b.address).then(b.score.cmp(&a.score)));
+    records.dedup_by_key(|record| record.address);
+    let v4 = records.partition_point(|record| record.address.is_ipv4());
+
+    let mut data =
+        Vec::with_capacity(HEADER_LEN + v4 * V4_RECORD_LEN + (records.len() - v4) * V6_RECORD_LEN);
+    data.extend_from_slice(&MAGIC);
+    data.extend_from_slice(&VERSION.to_le_bytes());
+    data.extend_from_slice(&0u32.to_le_bytes());
+    data.extend_from_slice(&to_timestamp(generated_at).to_le_bytes());
+    data.extend_from_slice(&(v4 as u64).to_le_bytes());
+    data.extend_from_slice(&((records.len() - v4) as u64).to_le_bytes());
+    data.resize(HEADER_LEN, 0);
+    for record in &records {
+        record.write(&mut data);
+    }
+
+    let checksum = checksum(&data);
+    data[40..48].copy_from_slice(&checksum.to_le_bytes());
+    data
+}
+
+/// Encodes a blacklist download, with the last report date of every entry.
+pub fn encode_blacklist(response: &Response) -> Vec<u8> {
+    encode(
+        response.meta().generated_at(),
+        response.entries().iter().map(|entry| Record {
+            address: entry.address(),
+            score: entry.abuse_confidence_score(),
+            last_reported_at: entry.last_reported_at(),
+        }),
+    )
+}
+
+/// Encodes a snapshot, which has no report dates.
+pub fn encode_snapshot(snapshot: &Snapshot) -> Vec<u8> {
+    encode(
+        snapshot.generated_at,
+        snapshot.entries.iter().map(|(address, score)| Record {
+            address: *address,
+            score: *score,
+            last_reported_at: None,
+        }),
+    )
+}
+
+/// Writes an encoded snapshot, replacing the file atomically. Processes that
+/// mapped the old file keep reading it until they open the new one.
+pub fn save<P: AsRef<Path>>(path: P, data: &[u8]) -> crate::Result<()> {
+    let path = path.as_ref();
+    let tmp = path.with_extension("tmp");
+    fs::write(&tmp, data)?;
+    fs::rename(tmp, path)?;
+    Ok(())
+}
+
+/// A binary snapshot read in place, from a memory mapped file or any other
+/// bytes. Lookups binary search the records without decoding the file.
+#[derive(Debug)]
+pub struct MappedSnapshot<B = Mmap> {
+    data: B,
+    v4: usize,
+    v6: usize,
+}
+
+impl MappedSnapshot<Mmap> {
+    /// Maps a file written by [`save`] and checks it. The file must only be
+    /// replaced, never modified in place, while it is mapped.
+    pub fn open<P: AsRef<Path>>(path: P) -> crate::Result<Self> {
+        let file = File::open(path)?;
+        // SAFETY: snapshots are written to a temporary file and renamed
+        // into place, so a mapped file is never modified
+        let data = unsafe { Mmap::map(&file)? };
+        Self::from_bytes(data)
+    }
+}
+
+impl<B: AsRef<[u8]>> MappedSnapshot<B> {
+    /// Checks the header, size and checksum of an encoded snapshot.
+    pub fn from_bytes(data: B) -> crate::Result<Self> {
+        let bytes = data.as_ref();
+        if bytes.len() < HEADER_LEN || bytes[..8] != MAGIC {
+            return invalid("not a binary snapshot");
+        }
+        let version = read_u32(bytes, 8);
+        if version != VERSION {
+            return invalid(&format!("unsupported version {version}"));
+        }
+
+        let v4 = read_u64(bytes, 24) as usize;
+        let v6 = read_u64(bytes, 32) as usize;
+        let len = v4
+            .checked_mul(V4_RECORD_LEN)
+            .zip(v6.checked_mul(V6_RECORD_LEN))
+            .and_then(|(v4, v6)| v4.checked_add(v6))
+            .and_then(|records| records.checked_add(HEADER_LEN));
+        if len != Some(bytes.len()) {
+            return invalid("truncated");
+        }
+        if read_u64(bytes, 40) != checksum(bytes) {
+            return invalid("checksum mismatch");
+        }
+
+        Ok(Self { data, v4, v6 })
+    }
+
+    fn v4_records(&self) -> &[u8] {
+        &self.data.as_ref()[HEADER_LEN..HEADER_LEN + self.v4 * V4_RECORD_LEN]
+    }
+
+    fn v6_records(&self) -> &[u8] {
+        &self.data.as_ref()[HEADER_LEN + self.v4 * V4_RECORD_LEN..]
+    }
+
+    pub fn generated_at(&self) -> Option<DateTime<Utc>> {
+        from_timestamp(read_i64(self.data.as_ref(), 16))
+    }
+
+    pub fn len(&self) -> usize {
+        self.v4 + self.v6
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// The record of a listed address. IPv4-mapped IPv6 addresses are
+    /// looked up as IPv4.
+    pub fn get(&self, address: IpAddr) -> Option<Record> {
+        match address.to_canonical() {
+            IpAddr::V4(address) => {
+                search(self.v4_records(), V4_RECORD_LEN, &address.octets()).map(Record::read_v4)
+            }
+            IpAddr::V6(address) => {
+                search(self.v6_records(), V6_RECORD_LEN, &address.octets()).map(Record::read_v6)
+            }
+        }
+    }
+
+    pub fn score(&self, address: IpAddr) -> Option<u32> {
+        self.get(address).map(|record| record.score)
+    }
+
+    pub fn contains(&self, address: IpAddr) -> bool {
+        self.get(address).is_some()
+    }
+
+    /// All records, IPv4 first, each family in ascending order.
+    pub fn records(&self) -> impl Iterator<Item = Record> + '_ {
+        let v4 = self
+            .v4_records()
+            .chunks_exact(V4_RECORD_LEN)
+            .map(Record::read_v4);
+        let v6 = self
+            .v6_records()
+            .chunks_exact(V6_RECORD_LEN)
+            .map(Record::read_v6);
+        v4.chain(v6)
+    }
+
+    pub fn to_snapshot(&self) -> Snapshot {
+        Snapshot {
+            generated_at: self.generated_at(),
+            entries: self
+                .records()
+                .map(|record| (record.address, record.score))
+                .collect(),
+        }
+    }
+}
+
+/// Binary searches fixed-width records starting with a big endian address.
+fn search<'a>(records: &'a [u8], width: usize, address: &[u8]) -> Option<&'a [u8]> {
+    let (mut low, mut high) = (0, records.len() / width);
+    while low < high {
+        let middle = low + (high - low) / 2;
+        let record = &records[middle * width..(middle + 1) * width];
+        match record[..address.len()].cmp(address) {
+            Ordering::Less => low = middle + 1,
+            Ordering::Greater => high = middle,
+            Ordering::Equal => return Some(record),
+        }
+    }
+    None
+}
@@ -0,0 +1,259 @@
+use std::{net::IpAddr, sync::OnceLock};
+
+use ipnetwork::IpNetwork;
+use regex::{Captures, Regex};
+
+/// The maximum comment length accepted by the API.
+pub const MAX_COMMENT_LENGTH: usize = 1024;
+
+fn email_regex() -> &'static Regex {
+    static REGEX: OnceLock<Regex> = OnceLock::new();
+    REGEX.get_or_init(|| Regex::new(r"[A-Za-z0-9._%+-]+@[A-Za-z0-9.-]+\.[A-Za-z]{2,}").unwrap())
+}
+
+fn ip_regex() -> &'static Regex {
+    static REGEX: OnceLock<Regex> = OnceLock::new();
+    REGEX.get_or_init(|| {
+        Regex::new(r"\b\d{1,3}(?:\.\d{1,3}){3}\b|[0-9A-Fa-f]{0,4}(?::[0-9A-Fa-f]{0,4}){2,7}(?:\.\d{1,3}){0,3}")
+            .unwrap()
+    })
+}
+
+fn username_regex() -> &'static Regex {
+    static REGEX: OnceLock<Regex> = OnceLock::new();
+    REGEX.get_or_init(|| {
+        Regex::new(
+            r#"(?i)(\b(?:user|username|sasl_username|ruser)(?:\s+|=|:\s*)["<]?|\bfor\s+(?:invalid\s+user\s+)?)([^\s"',;<>\[\]]+)(\s+from\b)?"#,
+        )
+        .unwrap()
+    })
+}
+
+/// Whether an address is loopback, private, link-local, shared (CGNAT),
+/// unique-local or unspecified, i.e. something that should never leave our
+/// network in a report comment.
+pub fn is_private(address: &IpAddr) -> bool {
+    match address {
+        IpAddr::V4(v4) => {
+            let octets = v4.octets();
+            v4.is_private()
+                || v4.is_loopback()
+                || v4.is_link_local()
+                || v4.is_unspecified()
+                || v4.is_broadcast()
+                || (octets[0] == 100 && (octets[1] & 0xc0) == 64)
+        }
+        IpAddr::V6(v6) => {
+            let first = v6.segments()[0];
+            if let Some(v4) = v6.to_ipv4_mapped() {
+                return is_private(&IpAddr::V4(v4));
+            }
+            v6.is_loopback()
+                || v6.is_unspecified()
+                || (first & 0xfe00) == 0xfc00
+                || (first & 0xffc0) == 0xfe80
+        }
+    }
+}
+
+/// Truncates `input` to at most `max_length` bytes without splitting a
+/// UTF-8 character.
+pub fn truncate(input: &str, max_length: usize) -> &str {
+    if input.len() <= max_length {
+        return input;
+    }
+    let mut end = max_length;
+    while !input.is_char_boundary(end) {
+        end -= 1;
+    }
+    &input[..end]
+}
+
+/// Scrubs report comments of personally identifiable information before
+/// they are sent to the API.
+///
+/// The default sanitizer redacts email addresses and private IPs, masks
+/// usernames and truncates to [`MAX_COMMENT_LENGTH`]. Own domains and
+/// networks have to be added explicitly.
+#[derive(Debug, Clone)]
+pub struct Sanitizer {
+    emails: bool,
+    private_ips: bool,
+    usernames: bool,
+    domains: Vec<String>,
+    domain_regex: Option<Regex>,
+    networks: Vec<IpNetwork>,
+    max_length: usize,
+}
+
+impl Default for Sanitizer {
+    fn default() -> Self {
+        Self {
+            emails: true,
+            private_ips: true,
+            usernames: true,
+            domains: Vec::new(),
+            domain_regex: None,
+            networks: Vec::new(),
+            max_length: MAX_COMMENT_LENGTH,
+        }
+    }
+}
+
+impl Sanitizer {
+    /// A sanitizer that only truncates.
+    pub fn none() -> Self {
+        Self {
+            emails: false,
+            private_ips: false,
+            usernames: false,
+            ..Self::default()
+        }
+    }
+
+    /// Replaces email addresses with `[email]`.
+    pub fn emails(mut self, enabled: bool) -> Self {
+        self.emails = enabled;
+        self
+    }
+
+    /// Replaces private, loopback and link-local addresses with `[ip]`.
+    pub fn private_ips(mut self, enabled: bool) -> Self {
+        self.private_ips = enabled;
+        self
+    }
+
+    /// Replaces usernames in common log phrases (`user root`, `user=root`,
+    /// `for root from`) with `[user]`.
+    pub fn usernames(mut self, enabled: bool) -> Self {
+        self.usernames = enabled;
+        self
+    }
+
+    /// Whether usernames are masked, for parsers that know where a username
+    /// is in formats the built-in patterns do not cover.
+    pub fn masks_usernames(&self) -> bool {
+        self.usernames
+    }
+
+    /// Replaces the domain and any of its subdomains with `[host]`.
+    pub fn domain<T: ToString>(mut self, domain: T) -> Self {
+        let domain = domain.to_string().trim_matches('.').to_ascii_lowercase();
+        if !domain.is_empty() {
+            self.domains.push(domain);
+        }
+        self.domain_regex = if self.domains.is_empty() {
+            None
+        } else {
+            let alternatives = self
+                .domains
+                .iter()
+                .map(|d| regex::escape(d))
+                .collect::<Vec<_>>()
+                .join("|");
+            Some(Regex::new(&format!(r"(?i)\b(?:[a-z0-9-]+\.)*(?:{alternatives})\b")).unwrap())
+        };
+        self
+    }
+
+    /// Replaces addresses inside the network with `[ip]`.
+    pub fn network(mut self, network: IpNetwork) -> Self {
+        self.networks.push(network);
+        self
+    }
+
+    /// Sets the maximum length in bytes, defaults to [`MAX_COMMENT_LENGTH`].
+    pub fn max_length(mut self, max_length: usize) -> Self {
+        self.max_length = max_length;
+        self
+    }
+
+    fn is_redacted_ip(&self, address: &IpAddr) -> bool {
+        (self.private_ips && is_private(address))
+            || self.networks.iter().any(|n| n.contains(*address))
+    }
+
+    /// Runs all enabled scrubbers and truncates the result.
+    pub fn sanitize(&self, input: &str) -> String {
+        let mut output = input.to_string();
+
+        if self.emails {
+            output = email_regex().replace_all(&output, "[email]").into_owned();
+        }
+
+        if let Some(regex) = &self.domain_regex {
+            output = regex.replace_all(&output, "[host]").into_owned();
+        }
+
+        if self.private_ips || !self.networks.is_empty() {
+            output = ip_regex()
+                .replace_all(&output, |caps: &Captures| {
+                    let matched = &caps[0];
+                    // the IPv6 pattern takes a following `:`, as in `fd00::5: lost`
+                    let (address, rest) = match matched.parse::<IpAddr>() {
+                        Ok(address) => (Ok(address), ""),
+                        Err(_) => {
+                            let trimmed = matched.trim_end_matches(':');
+                            (trimmed.parse::<IpAddr>(), &matched[trimmed.len()..])
+                        }
+                    };
+                    match address {
+                        Ok(address) if self.is_redacted_ip(&address) => format!("[ip]{rest}"),
+                        _ => matched.to_string(),
+                    }
+                })
+                .into_owned();
+        }
+
+        if self.usernames {
+            output = username_regex()
+                .replace_all(&output, |caps: &Captures| {
+                    let from = caps.get(3).map_or("", |m| m.as_str());
+                    let prefix = &caps[1];
+                    // an empty username, as in "Invalid user  from 1.2.3.4"
+                    if caps[2].eq_ignore_ascii_case("from") {
+                        return caps[0].to_string();
+                    }
+                    // "for" is only a username context in front of "from"
+                    if prefix.to_ascii_lowercase().starts_with("for") && from.is_empty() {
+                        return caps[0].to_string();
+                    }
+                    format!("{prefix}[user]{from}")
+                })
+                .into_owned();
+        }
+
+        truncate(output.trim(), self.max_length).to_string()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn redacts_ipv6_followed_by_colon() {
+        let sanitizer = Sanitizer::default();
+        assert_eq!(
+            sanitizer.sanitize("connect from fd00::5: lost"),
+            "connect from [ip]: lost"
+        );
+        assert_eq!(
+            sanitizer.sanitize("connect from [fd00::5]:22"),
+            "connect from [[ip]]:22"
+        );
+    }
+
+    #[test]
+    fn keeps_from_after_empty_username() {
+        let sanitizer = Sanitizer::default();
+        assert_eq!(
+            sanitizer.sanitize("Invalid user  from 203.0.113.1 port 22"),
+            "Invalid user  from 203.0.113.1 port 22"
+        );
+        assert_eq!(
+            sanitizer.sanitize("Failed password for invalid user admin from 203.0.113.1 port 22"),
+            "Failed password for invalid user [user] from 203.0.113.1 port 22"
+        );
+    }
+}
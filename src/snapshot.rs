@@ -0,0 +1,192 @@
+use std::{
+    collections::BTreeMap,
+    fmt::Write,
+    fs::{self, OpenOptions},
+    io::Write as _,
+    net::IpAddr,
+    path::Path,
+};
+
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+
+use crate::endpoints::blacklist::Response;
+
+/// A confidence score that changed between two snapshots.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ScoreChange {
+    pub address: IpAddr,
+    pub before: u32,
+    pub after: u32,
+}
+
+/// What changed from one snapshot to the next.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Diff {
+    /// When the previous snapshot was generated.
+    pub from: Option<DateTime<Utc>>,
+    /// When the current snapshot was generated.
+    pub to: Option<DateTime<Utc>>,
+    /// New addresses with their scores.
+    pub added: Vec<(IpAddr, u32)>,
+    /// Addresses that dropped off the list, with their last scores.
+    pub removed: Vec<(IpAddr, u32)>,
+    pub changed: Vec<ScoreChange>,
+}
+
+impl Diff {
+    pub fn is_empty(&self) -> bool {
+        self.added.is_empty() && self.removed.is_empty() && self.changed.is_empty()
+    }
+
+    /// A human readable change log: a summary line, then one line per
+    /// address, e.g. `+ 192.0.2.7 100`, `- 198.51.100.2 75` and
+    /// `~ 203.0.113.9 80 -> 95`.
+    pub fn change_log(&self) -> String {
+        let date = |d: Option<DateTime<Utc>>| d.map_or("unknown".to_string(), |d| d.to_rfc3339());
+        let mut log = format!(
+            "{} -> {}: {} added, {} removed, {} changed\n",
+            date(self.from),
+            date(self.to),
+            self.added.len(),
+            self.removed.len(),
+            self.changed.len()
+        );
+        for (address, score) in &self.added {
+            writeln!(log, "+ {address} {score}").unwrap();
+        }
+        for (address, score) in &self.removed {
+            writeln!(log, "- {address} {score}").unwrap();
+        }
+        for change in &self.changed {
+            writeln!(
+                log,
+                "~ {} {} -> {}",
+                change.address, change.before, change.after
+            )
+            .unwrap();
+        }
+        log
+    }
+
+    /// Appends the diff as one JSON line to a change feed.
+    pub fn append_to<P: AsRef<Path>>(&self, path: P) -> crate::Result<()> {
+        let mut line = serde_json::to_vec(self)?;
+        line.push(b'\n');
+        OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(path)?
+            .write_all(&line)?;
+        Ok(())
+    }
+}
+
+/// The addresses of a blacklist and their confidence scores, as applied to
+/// a firewall or exported to a file.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Snapshot {
+    pub generated_at: Option<DateTime<Utc>>,
+    pub entries: BTreeMap<IpAddr, u32>,
+}
+
+impl Snapshot {
+    pub fn from_response(response: &Response) -> Self {
+        Self {
+            generated_at: response.meta().generated_at(),
+            entries: response
+                .entries()
+                .iter()
+                .map(|e| (e.address(), e.abuse_confidence_score()))
+                .collect(),
+        }
+    }
+
+    /// Parses a plaintext blacklist, one address per line. The list has no
+    /// scores, so every address gets `score`, e.g. the `confidenceMinimum`
+    /// it was fetched with. Blank lines, `#` comments and invalid lines are
+    /// skipped.
+    pub fn from_plaintext(text: &str, score: u32) -> Self {
+        Self {
+            generated_at: None,
+            entries: text
+                .lines()
+                .map(str::trim)
+                .filter_map(|line| line.parse().ok())
+                .map(|address| (address, score))
+                .collect(),
+        }
+    }
+
+    /// Loads a snapshot saved by [`Snapshot::save`]. A missing file is an
+    /// empty snapshot.
+    pub fn load<P: AsRef<Path>>(path: P) -> crate::Result<Self> {
+        match fs::read(path) {
+            Ok(data) => Ok(serde_json::from_slice(&data)?),
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(Self::default()),
+            Err(e) => Err(e.into()),
+        }
+    }
+
+    /// Saves the snapshot as JSON, replacing the file atomically.
+    pub fn save<P: AsRef<Path>>(&self, path: P) -> crate::Result<()> {
+        let path = path.as_ref();
+        let tmp = path.with_extension("tmp");
+        fs::write(&tmp, serde_json::to_vec(self)?)?;
+        fs::rename(tmp, path)?;
+        Ok(())
+    }
+
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+
+    pub fn addresses(&self) -> impl Iterator<Item = IpAddr> + '_ {
+        self.entries.keys().copied()
+    }
+
+    /// What changed since `previous`.
+    pub fn diff(&self, previous: &Snapshot) -> Diff {
+        let mut diff = Diff {
+            from: previous.generated_at,
+            to: self.generated_at,
+            ..Diff::default()
+        };
+        for (address, score) in &self.entries {
+            match previous.entries.get(address) {
+                None => diff.added.push((*address, *score)),
+                Some(before) if before != score => diff.changed.push(ScoreChange {
+                    address: *address,
+                    before: *before,
+                    after: *score,
+                }),
+                Some(_) => {}
+            }
+        }
+        for (address, score) in &previous.entries {
+            if !self.entries.contains_key(address) {
+                diff.removed.push((*address, *score));
+            }
+        }
+        diff
+    }
+
+    /// Applies a diff made against this snapshot, e.g. one read from a
+    /// change feed, turning it into the newer snapshot.
+    pub fn apply(&mut self, diff: &Diff) {
+        for (address, _) in &diff.removed {
+            self.entries.remove(address);
+        }
+        for (address, score) in &diff.added {
+            self.entries.insert(*address, *score);
+        }
+        for change in &diff.changed {
+            self.entries.insert(change.address, change.after);
+        }
+        self.generated_at = diff.to;
+    }
+}
@@ -0,0 +1,347 @@
+use std::{
+    collections::BTreeMap,
+    fs::{self, File, OpenOptions},
+    io::{BufRead, Write},
+    net::IpAddr,
+    path::{Path, PathBuf},
+    sync::{Arc, Mutex},
+};
+
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use tokio::task::JoinHandle;
+
+use crate::{
+    endpoints::{bulk_report, report::Request},
+    types::Category,
+    Client, Error,
+};
+
+/// A queued report. The timestamp is fixed when the report is queued, so a
+/// report replayed hours later still carries the time of the abuse.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Entry {
+    pub id: u64,
+    pub address: IpAddr,
+    pub categories: Vec<Category>,
+    pub comment: Option<String>,
+    pub timestamp: DateTime<Utc>,
+    #[serde(default)]
+    pub attempts: u32,
+}
+
+impl Entry {
+    pub fn request(&self) -> Request {
+        Request {
+            address: self.address,
+            categories: self.categories.clone(),
+            comment: self.comment.clone(),
+            timestamp: Some(self.timestamp),
+        }
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(tag = "op", rename_all = "lowercase")]
+enum Record {
+    Push(Entry),
+    Attempt { id: u64 },
+    Ack { id: u64 },
+}
+
+/// An append-only JSONL queue of reports that could not be sent yet.
+///
+/// Every change is appended and synced before it is acknowledged, and an
+/// entry is only removed after the API accepted it, so a crash can cause a
+/// report to be sent twice but never lost.
+#[derive(Debug)]
+pub struct Spool {
+    path: PathBuf,
+    file: File,
+    entries: BTreeMap<u64, Entry>,
+    next_id: u64,
+    records: usize,
+}
+
+impl Spool {
+    /// Opens or creates the spool file and replays it.
+    pub fn open<P: AsRef<Path>>(path: P) -> crate::Result<Self> {
+        let path = path.as_ref().to_path_buf();
+        let mut entries = BTreeMap::new();
+        let mut next_id = 0;
+        let mut records = 0;
+
+        if path.exists() {
+            // a torn write from a crash leaves a last line without a newline,
+            // cut it off so the next append starts on a line of its own
+            let data = fs::read(&path)?;
+            let complete = data.iter().rposition(|b| *b == b'\n').map_or(0, |i| i + 1);
+            if complete < data.len() {
+                OpenOptions::new()
+                    .write(true)
+                    .open(&path)?
+                    .set_len(complete as u64)?;
+            }
+
+            for line in data[..complete].lines() {
+                let Ok(record) = serde_json::from_str::<Record>(&line?) else {
+                    continue;
+                };
+                records += 1;
+                match record {
+                    Record::Push(entry) => {
+                        next_id = next_id.max(entry.id + 1);
+                        entries.insert(entry.id, entry);
+                    }
+                    Record::Attempt { id } => {
+                        if let Some(entry) = entries.get_mut(&id) {
+                            entry.attempts += 1;
+                        }
+                    }
+                    Record::Ack { id } => {
+                        entries.remove(&id);
+                    }
+                }
+            }
+        }
+
+        let file = OpenOptions::new().create(true).append(true).open(&path)?;
+
+        Ok(Self {
+            path,
+            file,
+            entries,
+            next_id,
+            records,
+        })
+    }
+
+    fn append(&mut self, record: &Record) -> crate::Result<()> {
+        let mut line = serde_json::to_vec(record)?;
+        line.push(b'\n');
+        self.file.write_all(&line)?;
+        self.file.sync_data()?;
+        self.records += 1;
+        Ok(())
+    }
+
+    /// Queues a request. Requests without a timestamp are dated now.
+    pub fn push(&mut self, request: Request) -> crate::Result<u64> {
+        let entry = Entry {
+            id: self.next_id,
+            address: request.address,
+            categories: request.categories,
+            comment: request.comment,
+            timestamp: request.timestamp.unwrap_or_else(Utc::now),
+            attempts: 0,
+        };
+        self.next_id += 1;
+
+        let id = entry.id;
+        self.append(&Record::Push(entry.clone()))?;
+        self.entries.insert(id, entry);
+        Ok(id)
+    }
+
+    /// Records a failed delivery attempt.
+    pub fn attempt(&mut self, id: u64) -> crate::Result<()> {
+        if let Some(entry) = self.entries.get_mut(&id) {
+            entry.attempts += 1;
+            self.append(&Record::Attempt { id })?;
+        }
+        Ok(())
+    }
+
+    /// Removes a delivered (or permanently rejected) entry.
+    pub fn ack(&mut self, id: u64) -> crate::Result<()> {
+        if self.entries.remove(&id).is_some() {
+            self.append(&Record::Ack { id })?;
+        }
+        Ok(())
+    }
+
+    /// Queued entries, oldest first.
+    pub fn entries(&self) -> impl Iterator<Item = &Entry> {
+        self.entries.values()
+    }
+
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+
+    /// Rewrites the file with only the queued entries.
+    pub fn compact(&mut self) -> crate::Result<()> {
+        let tmp = self.path.with_extension("tmp");
+        let mut data = Vec::new();
+        for entry in self.entries.values() {
+            serde_json::to_writer(&mut data, &Record::Push(entry.clone()))?;
+            data.push(b'\n');
+        }
+        fs::write(&tmp, data)?;
+        File::open(&tmp)?.sync_all()?;
+        fs::rename(&tmp, &self.path)?;
+
+        self.file = OpenOptions::new().append(true).open(&self.path)?;
+        self.records = self.entries.len();
+        Ok(())
+    }
+
+    /// Compacts once acknowledged records make up most of the file.
+    fn maybe_compact(&mut self) -> crate::Result<()> {
+        if self.records > 1024 && self.records > self.entries.len() * 4 {
+            self.compact()?;
+        }
+        Ok(())
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct DrainOptions {
+    /// How long to wait between drains when nothing went wrong.
+    pub interval: std::time::Duration,
+    /// How long to wait after a network or server error.
+    pub backoff: std::time::Duration,
+    /// Entries rejected by the API this many times are dropped.
+    pub max_attempts: u32,
+    /// Backlogs of at least this size are sent through `bulk-report`.
+    pub bulk_threshold: usize,
+}
+
+impl Default for DrainOptions {
+    fn default() -> Self {
+        Self {
+            interval: std::time::Duration::from_secs(60),
+            backoff: std::time::Duration::from_secs(30),
+            max_attempts: 5,
+            bulk_threshold: 100,
+        }
+    }
+}
+
+/// The result of a single [`drain`] pass.
+#[derive(Debug, Clone, Default)]
+pub struct Drained {
+    pub sent: usize,
+    pub dropped: usize,
+    /// Set when the pass stopped early; retry no earlier than this.
+    pub retry_after: Option<std::time::Duration>,
+}
+
+fn lock(spool: &Mutex<Spool>) -> std::sync::MutexGuard<'_, Spool> {
+    spool.lock().unwrap_or_else(|e| e.into_inner())
+}
+
+/// Sends queued entries until the spool is empty or the API stops
+/// accepting them.
+pub async fn drain(
+    spool: &Mutex<Spool>,
+    client: &Client,
+    options: &DrainOptions,
+) -> crate::Result<Drained> {
+    let entries: Vec<Entry> = lock(spool).entries().cloned().collect();
+    let mut drained = Drained::default();
+
+    if entries.len() >= options.bulk_threshold {
+        for chunk in entries.chunks(bulk_report::MAX_ROWS) {
+            let requests: Vec<Request> = chunk.iter().map(Entry::request).collect();
+            match client.bulk_report(&requests).await {
+                Ok(response) => {
+                    let mut guard = lock(spool);
+                    for entry in chunk {
+                        guard.ack(entry.id)?;
+                    }
+                    drained.sent += response.saved_reports() as usize;
+                    drained.dropped += response.invalid_reports().len();
+                }
+                Err(e) if e.is_retryable() => {
+                    drained.retry_after = Some(retry_delay(&e, options));
+                    break;
+                }
+                Err(_) => {
+                    // e.g. a chunk the API could not parse, so it does not
+                    // block the rest of the backlog forever
+                    let mut guard = lock(spool);
+                    for entry in chunk {
+                        reject(&mut guard, entry, options, &mut drained)?;
+                    }
+                }
+            }
+        }
+    } else {
+        for entry in &entries {
+            match client.send_report(&entry.request()).await {
+                Ok(_) => {
+                    lock(spool).ack(entry.id)?;
+                    drained.sent += 1;
+                }
+                Err(e) if e.is_retryable() => {
+                    drained.retry_after = Some(retry_delay(&e, options));
+                    break;
+                }
+                Err(_) => reject(&mut lock(spool), entry, options, &mut drained)?,
+            }
+        }
+    }
+
+    lock(spool).maybe_compact()?;
+    Ok(drained)
+}
+
+/// Counts a rejection, dropping the entry once it reached `max_attempts`.
+fn reject(
+    spool: &mut Spool,
+    entry: &Entry,
+    options: &DrainOptions,
+    drained: &mut Drained,
+) -> crate::Result<()> {
+    if entry.attempts + 1 >= options.max_attempts {
+        spool.ack(entry.id)?;
+        drained.dropped += 1;
+    } else {
+        spool.attempt(entry.id)?;
+    }
+    Ok(())
+}
+
+fn retry_delay(error: &Error, options: &DrainOptions) -> std::time::Duration {
+    match error {
+        Error::RateLimit {
+            retry_after, reset, ..
+        } => {
+            let delay = if *retry_after > chrono::Duration::zero() {
+                *retry_after
+            } else {
+                *reset - Utc::now()
+            };
+            delay.to_std().unwrap_or(options.backoff)
+        }
+        _ => options.backoff,
+    }
+}
+
+/// Spawns a task that drains the spool forever, sleeping for the rate
+/// limit's retry-after or the configured backoff whenever the API is not
+/// accepting reports.
+pub fn spawn_drain(
+    spool: Arc<Mutex<Spool>>,
+    client: Client,
+    options: DrainOptions,
+) -> JoinHandle<()> {
+    tokio::spawn(async move {
+        loop {
+            let delay = match drain(&spool, &client, &options).await {
+                Ok(Drained {
+                    retry_after: Some(delay),
+                    ..
+                }) => delay,
+                Ok(_) => options.interval,
+                Err(_) => options.backoff,
+            };
+            tokio::time::sleep(delay).await;
+        }
+    })
+}
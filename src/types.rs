@@ -1,6 +1,8 @@
 use chrono::Duration;
 use serde::{Deserialize, Serialize};
 
+pub use crate::country::CountryCode;
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct DataWrapper<T> {
     pub data: T,
@@ -17,7 +19,7 @@ where
     serializer.serialize_str(&datetime.to_rfc3339())
 }
 
-#[derive(Debug, Clone, Copy, PartialEq, Deserialize, Serialize)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Deserialize, Serialize)]
 #[repr(u8)]
 pub enum Category {
     /// Altering DNS records resulting in improper redirection.
@@ -82,6 +84,53 @@ pub enum Category {
     IotTargeted = 23,
 }
 
+impl TryFrom<u8> for Category {
+    type Error = u8;
+
+    fn try_from(id: u8) -> Result<Self, u8> {
+        match id {
+            1 => Ok(Category::DnsCompromise),
+            2 => Ok(Category::DnsPoisoning),
+            3 => Ok(Category::FraudOrder),
+            4 => Ok(Category::DdosAttack),
+            5 => Ok(Category::FtpBruteForce),
+            6 => Ok(Category::PingOfDeath),
+            7 => Ok(Category::Phishing),
+            8 => Ok(Category::FraudVoip),
+            9 => Ok(Category::OpenProxy),
+            10 => Ok(Category::WebSpam),
+            11 => Ok(Category::EmailSpam),
+            12 => Ok(Category::BlogSpam),
+            13 => Ok(Category::VpnIp),
+            14 => Ok(Category::PortScan),
+            15 => Ok(Category::Hacking),
+            16 => Ok(Category::SqlInjection),
+            17 => Ok(Category::Spoofing),
+            18 => Ok(Category::BruteForceCredential),
+            19 => Ok(Category::BadWebBot),
+            20 => Ok(Category::ExploitedHost),
+            21 => Ok(Category::WebAppAttack),
+            22 => Ok(Category::SshAbuse),
+            23 => Ok(Category::IotTargeted),
+            _ => Err(id),
+        }
+    }
+}
+
+/// Parses a comma separated list of category ids such as `"18,22"`, the
+/// format used by the API and by fail2ban's `abuseipdb_category`.
+pub fn parse_categories(s: &str) -> Option<Vec<Category>> {
+    s.split(',')
+        .map(str::trim)
+        .filter(|part| !part.is_empty())
+        .map(|part| {
+            part.parse()
+                .ok()
+                .and_then(|id: u8| Category::try_from(id).ok())
+        })
+        .collect()
+}
+
 // serde serialize vec of categories to comma separated string, by using the repr of the enum
 pub(crate) fn serde_categories_to_string<S>(
     categories: &[Category],
@@ -95,7 +144,7 @@ where
         if !s.is_empty() {
             s.push(',');
         }
-        s.push_str(&(category.clone() as u8).to_string());
+        s.push_str(&(*category as u8).to_string());
     }
     serializer.serialize_str(&s)
 }
@@ -104,34 +153,12 @@ pub(crate) fn serde_vec_to_categories<'de, D>(deserializer: D) -> Result<Vec<Cat
 where
     D: serde::Deserializer<'de>,
 {
-    let s = Vec::deserialize(deserializer)?;
+    let s: Vec<u8> = Vec::deserialize(deserializer)?;
     let mut categories = Vec::new();
     for part in s {
-        match part {
-            1 => categories.push(Category::DnsCompromise),
-            2 => categories.push(Category::DnsPoisoning),
-            3 => categories.push(Category::FraudOrder),
-            4 => categories.push(Category::DdosAttack),
-            5 => categories.push(Category::FtpBruteForce),
-            6 => categories.push(Category::PingOfDeath),
-            7 => categories.push(Category::Phishing),
-            8 => categories.push(Category::FraudVoip),
-            9 => categories.push(Category::OpenProxy),
-            10 => categories.push(Category::WebSpam),
-            11 => categories.push(Category::EmailSpam),
-            12 => categories.push(Category::BlogSpam),
-            13 => categories.push(Category::VpnIp),
-            14 => categories.push(Category::PortScan),
-            15 => categories.push(Category::Hacking),
-            16 => categories.push(Category::SqlInjection),
-            17 => categories.push(Category::Spoofing),
-            18 => categories.push(Category::BruteForceCredential),
-            19 => categories.push(Category::BadWebBot),
-            20 => categories.push(Category::ExploitedHost),
-            21 => categories.push(Category::WebAppAttack),
-            22 => categories.push(Category::SshAbuse),
-            23 => categories.push(Category::IotTargeted),
-            _ => return Err(serde::de::Error::custom("invalid category")),
+        match Category::try_from(part) {
+            Ok(category) => categories.push(category),
+            Err(_) => return Err(serde::de::Error::custom("invalid category")),
         }
     }
 
@@ -169,10 +196,20 @@ pub struct Report {
     #[serde(deserialize_with = "serde_vec_to_categories")]
     categories: Vec<Category>,
     reporter_id: u32,
-    reporter_country_code: String,
+    #[serde(
+        default,
+        deserialize_with = "crate::country::serde_country_code_from_str"
+    )]
+    reporter_country_code: Option<CountryCode>,
     reporter_country_name: String,
 }
 
+impl Report {
+    pub fn reporter_country_code(&self) -> Option<CountryCode> {
+        self.reporter_country_code
+    }
+}
+
 #[derive(Debug, Clone, Serialize)]
 pub enum IpVersion {
     V4,